@@ -2,30 +2,388 @@
 
 use ink_lang as ink;
 
+pub mod psp22;
+
 #[ink::contract]
 mod token {
     use ink_storage::{
-        traits::SpreadAllocate,
+        traits::{
+            PackedLayout,
+            SpreadAllocate,
+            SpreadLayout,
+        },
         Mapping,
     };
 
     use ink_lang as ink;
+    use ink_prelude::{
+        string::String,
+        vec::Vec,
+    };
+
+    use crate::psp22::{
+        PSP22Error,
+        PSP22,
+    };
 
     #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
     pub enum Error {
         InsufficientBalance,
         InsufficientAllowance,
+        Overflow,
+        NotOwner,
+        Paused,
+        CapExceeded,
+        LengthMismatch,
+        ZeroAddress,
+        NotMinter,
+        AccountFrozen,
+        UpgradeFailed,
+        AllowanceChanged,
+        InvalidSnapshot,
+        PermitExpired,
+        InvalidSignature,
+        NotPendingOwner,
+        FeeTooHigh,
+        Reentrancy,
+        TransferRejected,
+        TokensLocked,
+        VestingAlreadyExists,
+        NothingToRelease,
+        CooldownActive,
+        FaucetDisabled,
+        NoEligibleHolders,
+        NoDividends,
+        NativeTransferFailed,
+        ExceedsMaxTx,
+        ExceedsMaxWallet,
+        Expired,
+        InvalidFraction,
+        CallFailed,
+        InvalidProof,
+        AlreadyClaimed,
+        BelowMinimum,
+        SelfApproval,
+        NothingStaked,
+        InvalidCoupon,
+        NotWhitelisted,
+        FlashLoanNotRepaid,
+        RateLimited,
+        NonceUsed,
+        AlreadyProcessed,
+        ZeroAmount,
+        Underflow,
+        MinterCapExceeded,
+        TimelockNotElapsed,
+        ActionNotQueued,
+        NotRecoveryAuthority,
+        ParamsMismatch,
+        ReservedAction,
+        TimelockDisabled,
+        NothingOwed,
+    }
+
+    impl Error {
+        /// Returns a short human-readable description of the error.
+        pub fn message(&self) -> &'static str {
+            match self {
+                Error::InsufficientBalance => "insufficient balance",
+                Error::InsufficientAllowance => "insufficient allowance",
+                Error::Overflow => "arithmetic overflow",
+                Error::NotOwner => "caller is not the owner",
+                Error::Paused => "operation is paused",
+                Error::CapExceeded => "supply cap exceeded",
+                Error::LengthMismatch => "input lengths do not match",
+                Error::ZeroAddress => "zero address not allowed",
+                Error::NotMinter => "caller is not a minter",
+                Error::AccountFrozen => "account is frozen",
+                Error::UpgradeFailed => "code upgrade failed",
+                Error::AllowanceChanged => "allowance changed",
+                Error::InvalidSnapshot => "invalid snapshot id",
+                Error::PermitExpired => "permit expired",
+                Error::InvalidSignature => "invalid signature",
+                Error::NotPendingOwner => "caller is not the pending owner",
+                Error::FeeTooHigh => "fee too high",
+                Error::Reentrancy => "reentrant call",
+                Error::TransferRejected => "transfer rejected by recipient",
+                Error::TokensLocked => "tokens are locked",
+                Error::VestingAlreadyExists => "vesting schedule already exists",
+                Error::NothingToRelease => "nothing to release",
+                Error::CooldownActive => "cooldown still active",
+                Error::FaucetDisabled => "faucet is disabled",
+                Error::NoEligibleHolders => "no eligible holders",
+                Error::NoDividends => "no dividends to withdraw",
+                Error::NativeTransferFailed => "native transfer failed",
+                Error::ExceedsMaxTx => "amount exceeds the per-transaction maximum",
+                Error::ExceedsMaxWallet => "recipient balance would exceed the wallet maximum",
+                Error::Expired => "deadline passed",
+                Error::InvalidFraction => "fractional part out of range",
+                Error::CallFailed => "cross-contract call failed",
+                Error::InvalidProof => "merkle proof does not match the airdrop root",
+                Error::AlreadyClaimed => "airdrop already claimed",
+                Error::BelowMinimum => "transfer is below the minimum amount",
+                Error::SelfApproval => "owner cannot approve itself as spender",
+                Error::NothingStaked => "account has nothing staked",
+                Error::InvalidCoupon => "coupon does not exist or was already redeemed",
+                Error::NotWhitelisted => "account is not whitelisted",
+                Error::FlashLoanNotRepaid => "flash loan and fee were not repaid",
+                Error::RateLimited => "too many transfers from this account in this block",
+                Error::NonceUsed => "mint authorization nonce already used",
+                Error::AlreadyProcessed => "bridge transaction already processed",
+                Error::ZeroAmount => "zero-value transfers are disabled",
+                Error::Underflow => "arithmetic underflow",
+                Error::MinterCapExceeded => "minter issuance cap exceeded",
+                Error::TimelockNotElapsed => "timelock delay has not elapsed",
+                Error::ActionNotQueued => "action is not queued",
+                Error::NotRecoveryAuthority => "caller is not the recovery authority",
+                Error::ParamsMismatch => "arguments do not match the queued action",
+                Error::ReservedAction => "action is executed by its own message",
+                Error::TimelockDisabled => "timelock delay is zero",
+                Error::NothingOwed => "no staking reward is owed",
+            }
+        }
+    }
+
+    impl From<Error> for PSP22Error {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InsufficientBalance => PSP22Error::InsufficientBalance,
+                Error::InsufficientAllowance => PSP22Error::InsufficientAllowance,
+                Error::ZeroAddress => PSP22Error::ZeroRecipientAddress,
+                Error::TransferRejected => PSP22Error::SafeTransferCheckFailed(String::from(error.message())),
+                error => PSP22Error::Custom(String::from(error.message())),
+            }
+        }
     }
 
     pub type Result<T> = core::result::Result<T, Error>;
 
+    /// Highest transfer fee the owner may configure, in basis points (10%).
+    pub const MAX_FEE_BPS: u16 = 1000;
+
+    /// Selector of the `on_received(operator, from, value, data)` message a
+    /// receiving contract implements in order to be notified of transfers.
+    pub const ON_RECEIVED_SELECTOR: [u8; 4] = ink::selector_bytes!("on_received");
+
+    /// Selector of the `on_flash_loan(initiator, amount, fee, data)` message
+    /// a flash loan receiver implements.
+    pub const ON_FLASH_LOAN_SELECTOR: [u8; 4] = ink::selector_bytes!("on_flash_loan");
+
+    /// Value `on_flash_loan` must return to accept a flash loan, as in EIP-3156.
+    pub const ON_FLASH_LOAN_SUCCESS: [u8; 32] = ink::blake2x256!("ERC3156FlashBorrower.onFlashLoan");
+
+    /// Selector of `PSP22::transfer(to, value, data)` on another PSP22 token.
+    pub const PSP22_TRANSFER_SELECTOR: [u8; 4] = ink::selector_bytes!("PSP22::transfer");
+
+    /// Raising the combined fee, tax and burn rate above this many basis
+    /// points must go through the timelock.
+    pub const TIMELOCK_FEE_BPS: u16 = 500;
+
+    /// Timelocked action executed by `upgrade(code_hash)`.
+    pub const ACTION_UPGRADE: u32 = 1;
+
+    /// Timelocked action executed by `set_fee(fee_bps, collector)` above
+    /// `TIMELOCK_FEE_BPS`.
+    pub const ACTION_SET_FEE: u32 = 2;
+
+    /// Timelocked action executed by `set_timelock_delay(delay)` when it
+    /// shortens the delay.
+    pub const ACTION_LOWER_TIMELOCK: u32 = 3;
+
+    /// Timelocked action executed by `set_tax(tax_bps, burn_share_bps)`
+    /// above `TIMELOCK_FEE_BPS`.
+    pub const ACTION_SET_TAX: u32 = 4;
+
+    /// Timelocked action executed by `set_burn_rate(burn_rate_bps)` above
+    /// `TIMELOCK_FEE_BPS`.
+    pub const ACTION_SET_BURN_RATE: u32 = 5;
+
+    /// An allowance of this size never decreases when it is spent.
+    pub const UNLIMITED_ALLOWANCE: Balance = Balance::MAX;
+
+    /// Scaling factor for `magnified_dividend_per_share`, keeping the
+    /// rounding error of each deposit below one base unit per 10^12 tokens.
+    pub const DIVIDEND_MAGNITUDE: Balance = 1_000_000_000_000;
+
+    /// Semantic version of this contract code, as `(major, minor, patch)`.
+    pub const VERSION: (u16, u16, u16) = (0, 1, 0);
+
+    /// Version of the `Token` storage layout this code expects. Bump it
+    /// whenever an upgrade needs `migrate` to rewrite existing storage.
+    pub const STORAGE_VERSION: u16 = 1;
+
+    /// Period over which `stake_reward_bps` accrues, in milliseconds (one day).
+    pub const STAKE_REWARD_PERIOD: u64 = 86_400_000;
+
+    /// Upper bound on the reward of a single stake, in basis points of its
+    /// principal (100%), however long it is held.
+    pub const MAX_STAKE_REWARD_BPS: Balance = 10_000;
+
+    /// Narrows `value` to a `ConfigChanged` value, saturating at `u64::MAX`.
+    fn saturate(value: Balance) -> u64 {
+        u64::try_from(value).unwrap_or(u64::MAX)
+    }
+
+    /// Narrows an optional limit to a `ConfigChanged` value; no limit reads
+    /// as `u64::MAX`.
+    fn limit_value(limit: Option<Balance>) -> u64 {
+        limit.map_or(u64::MAX, saturate)
+    }
+
+    /// What the recipient of `value` receives once `fee` and `burn` are taken.
+    fn net_of(value: Balance, fee: Balance, burn: Balance) -> Result<Balance> {
+        value
+            .checked_sub(fee)
+            .and_then(|net| net.checked_sub(burn))
+            .ok_or(Error::Underflow)
+    }
+
+    /// Widens `value` for the signed dividend arithmetic.
+    fn signed(value: Balance) -> Result<i128> {
+        i128::try_from(value).map_err(|_| Error::Overflow)
+    }
+
+    /// Bytes of an optional account for `ConfigBytesChanged`; `None` reads as
+    /// all zeros.
+    fn account_bytes(account: Option<AccountId>) -> [u8; 32] {
+        account.map_or([0; 32], |account| *account.as_ref())
+    }
+
+    /// Order of magnitude of `value` (`floor(log10(value))`, 0 for zero),
+    /// published as an event topic so indexers can filter large amounts.
+    pub fn value_bucket(value: Balance) -> u8 {
+        value.checked_ilog10().unwrap_or(0) as u8
+    }
+
+    /// Tokens held by the contract on behalf of `beneficiary` and released
+    /// linearly over `duration` milliseconds from `start_ts`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct VestingSchedule {
+        pub total: Balance,
+        pub start_ts: u64,
+        pub duration: u64,
+        pub released: Balance,
+    }
+
+    impl VestingSchedule {
+        fn vested_at(&self, now: u64) -> Result<Balance> {
+            if now < self.start_ts {
+                return Ok(0);
+            }
+            let elapsed = now - self.start_ts;
+            if elapsed >= self.duration {
+                return Ok(self.total);
+            }
+
+            let vested = self
+                .total
+                .checked_mul(elapsed.into())
+                .ok_or(Error::Overflow)?
+                / Balance::from(self.duration);
+            Ok(vested)
+        }
+    }
+
     #[ink(storage)]
     #[derive(SpreadAllocate)]
     pub struct Token {
-        total_supply: u32,
-        balances: Mapping<AccountId, u32>,
-        allowances: Mapping<(AccountId, AccountId), u32>,
+        total_supply: Balance,
+        balances: Mapping<AccountId, Balance>,
+        allowances: Mapping<(AccountId, AccountId), Balance>,
+        owner: AccountId,
+        name: Option<String>,
+        symbol: Option<String>,
+        /// Set once by the constructor. No message may write it: every stored
+        /// balance is denominated in it, so changing it would rescale them all.
+        decimals: u8,
+        transfers_paused: bool,
+        cap: Balance,
+        reject_zero_address: bool,
+        minters: Mapping<AccountId, ()>,
+        frozen: Mapping<AccountId, ()>,
+        snapshots: Mapping<(AccountId, u32), Balance>,
+        current_snapshot_id: u32,
+        nonces: Mapping<AccountId, u64>,
+        pending_owner: Option<AccountId>,
+        fee_bps: u16,
+        fee_collector: AccountId,
+        /// Held while a guarded call runs. A `Mapping` entry is written to
+        /// storage at once, whereas plain fields are only stored when the
+        /// message returns, so a nested call from another contract sees it.
+        reentrancy_lock: Mapping<(), ()>,
+        total_minted: Balance,
+        total_burned: Balance,
+        locks: Mapping<AccountId, (Balance, u64)>,
+        vesting: Mapping<AccountId, VestingSchedule>,
+        faucet_amount: Balance,
+        faucet_cooldown: u64,
+        last_claim: Mapping<AccountId, u64>,
+        magnified_dividend_per_share: Balance,
+        dividend_corrections: Mapping<AccountId, i128>,
+        withdrawn_dividends: Mapping<AccountId, Balance>,
+        max_tx_amount: Option<Balance>,
+        max_wallet: Option<Balance>,
+        holder_count: u32,
+        allowance_log: Mapping<u32, (AccountId, AccountId)>,
+        allowance_log_len: u32,
+        logged_pairs: Mapping<(AccountId, AccountId), ()>,
+        escrowed: Balance,
+        cap_reached: bool,
+        /// Position of each excluded account in `excluded_accounts`.
+        excluded: Mapping<AccountId, u32>,
+        excluded_accounts: Mapping<u32, AccountId>,
+        excluded_count: u32,
+        merkle_root: [u8; 32],
+        claimed: Mapping<AccountId, ()>,
+        min_transfer: Balance,
+        staked: Mapping<AccountId, (Balance, u64)>,
+        stake_reward_bps: u16,
+        mint_paused: bool,
+        coupons: Mapping<[u8; 32], Balance>,
+        whitelist_only: bool,
+        whitelist: Mapping<AccountId, ()>,
+        storage_version: u16,
+        transfers_this_block: Mapping<(AccountId, u32), u32>,
+        max_transfers_per_block: u32,
+        batch_events: bool,
+        batching: bool,
+        authorizer: Option<AccountId>,
+        used_mint_nonces: Mapping<u64, ()>,
+        processed_bridge_txs: Mapping<[u8; 32], ()>,
+        allowance_expiries: Mapping<(AccountId, AccountId), u64>,
+        spent: Mapping<(AccountId, AccountId), Balance>,
+        allow_zero_transfers: bool,
+        burn_rate_bps: u16,
+        use_zero_for_mint: bool,
+        last_trade: Mapping<AccountId, u64>,
+        trade_cooldown: u64,
+        /// Sum of all balances, kept up to date by `set_balance`.
+        tracked_supply: Balance,
+        tax_bps: u16,
+        burn_share_bps: u16,
+        minter_cap: Mapping<AccountId, Balance>,
+        minter_minted: Mapping<AccountId, Balance>,
+        queued_actions: Mapping<u32, ([u8; 32], u64)>,
+        timelock_delay: u64,
+        /// Set once by the constructor. The only account that may move tokens
+        /// it does not own (`force_transfer` and the rescue messages). The
+        /// zero account, the default, disables them.
+        recovery_authority: AccountId,
+        holder_log: Mapping<u32, AccountId>,
+        holder_log_len: u32,
+        logged_holders: Mapping<AccountId, ()>,
+        round_fees_up: bool,
+        /// Staking rewards that fell due while minting was paused.
+        owed_stake_rewards: Mapping<AccountId, Balance>,
+        /// Per account, the ids of the snapshots it has an entry in
+        /// `snapshots` for, in increasing order.
+        snapshot_ids: Mapping<(AccountId, u32), u32>,
+        snapshot_id_count: Mapping<AccountId, u32>,
     }
 
     #[ink(event)]
@@ -34,7 +392,17 @@ mod token {
         from: Option<AccountId>,
         #[ink(topic)]
         to: Option<AccountId>,
-        value: u32,
+        value: Balance,
+        #[ink(topic)]
+        value_bucket: u8,
+    }
+
+    #[ink(event)]
+    pub struct TransferBatch {
+        #[ink(topic)]
+        from: AccountId,
+        to_list: Vec<AccountId>,
+        values: Vec<Balance>,
     }
 
     #[ink(event)]
@@ -43,145 +411,5612 @@ mod token {
         owner: AccountId,
         #[ink(topic)]
         spender: AccountId,
-        value: u32,
+        value: Balance,
+        #[ink(topic)]
+        value_bucket: u8,
+    }
+
+    #[ink(event)]
+    pub struct Mint {
+        #[ink(topic)]
+        to: AccountId,
+        value: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Burn {
+        #[ink(topic)]
+        from: AccountId,
+        value: Balance,
+        memo: [u8; 32],
+    }
+
+    #[ink(event)]
+    pub struct ForcedTransfer {
+        #[ink(topic)]
+        from: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        value: Balance,
+    }
+
+    #[ink(event)]
+    pub struct BridgeLocked {
+        #[ink(topic)]
+        from: AccountId,
+        amount: Balance,
+        #[ink(topic)]
+        dest_chain: u32,
+        dest_addr: [u8; 32],
+    }
+
+    #[ink(event)]
+    pub struct BridgeUnlocked {
+        #[ink(topic)]
+        to: AccountId,
+        amount: Balance,
+        #[ink(topic)]
+        src_tx: [u8; 32],
+    }
+
+    #[ink(event)]
+    pub struct CapReached {
+        cap: Balance,
+    }
+
+    #[ink(event)]
+    pub struct TokensReleased {
+        #[ink(topic)]
+        beneficiary: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Paused {
+        account: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct Unpaused {
+        account: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct MinterGranted {
+        #[ink(topic)]
+        account: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct MinterRevoked {
+        #[ink(topic)]
+        account: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct CodeUpgraded {
+        new_hash: [u8; 32],
+    }
+
+    #[ink(event)]
+    pub struct Snapshot {
+        id: u32,
+    }
+
+    #[ink(event)]
+    pub struct OwnershipTransferStarted {
+        #[ink(topic)]
+        previous_owner: AccountId,
+        #[ink(topic)]
+        new_owner: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct OwnershipTransferred {
+        #[ink(topic)]
+        previous_owner: AccountId,
+        #[ink(topic)]
+        new_owner: AccountId,
+    }
+
+    /// An owner-only setting changed. `key` is the setting's ASCII name,
+    /// zero-padded to 8 bytes; values wider than `u64` saturate, `None`
+    /// limits read as `u64::MAX` and flags as 0 or 1.
+    #[ink(event)]
+    pub struct ConfigChanged {
+        #[ink(topic)]
+        key: [u8; 8],
+        old: u64,
+        new: u64,
+        #[ink(topic)]
+        by: AccountId,
+    }
+
+    /// Like `ConfigChanged`, for owner-only settings holding an account or
+    /// a hash. Accounts are given by their 32 bytes; an unset account or
+    /// hash reads as all zeros.
+    #[ink(event)]
+    pub struct ConfigBytesChanged {
+        #[ink(topic)]
+        key: [u8; 8],
+        old: [u8; 32],
+        new: [u8; 32],
+        #[ink(topic)]
+        by: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct ActionQueued {
+        #[ink(topic)]
+        action_id: u32,
+        params_hash: [u8; 32],
+        eta: u64,
+    }
+
+    #[ink(event)]
+    pub struct ActionExecuted {
+        #[ink(topic)]
+        action_id: u32,
+        params_hash: [u8; 32],
+    }
+
+    #[ink(event)]
+    pub struct ActionCancelled {
+        #[ink(topic)]
+        action_id: u32,
+    }
+
+    #[ink(event)]
+    pub struct NonceInvalidated {
+        #[ink(topic)]
+        owner: AccountId,
+        nonce: u64,
     }
 
     impl Token {
+        /// Deploys a fixed supply: the cap equals `initial_supply`, so nothing
+        /// can be minted later. Use `new_capped` to leave room for minting.
+        #[ink(constructor)]
+        pub fn new(initial_supply: Balance) -> Self {
+            Self::new_capped(initial_supply, initial_supply)
+        }
+
+        /// Like `new`, but panics if `initial_supply` is below `min_supply`, to
+        /// catch deployments with a missing or mis-scaled supply.
+        #[ink(constructor)]
+        pub fn new_with_min_supply(initial_supply: Balance, min_supply: Balance) -> Self {
+            assert!(initial_supply >= min_supply, "initial supply below minimum");
+            Self::new(initial_supply)
+        }
+
+        /// Deploys `initial_supply` with the given metadata. Unlike `new`, the
+        /// supply is uncapped.
+        #[ink(constructor)]
+        pub fn new_with_metadata(
+            initial_supply: Balance,
+            name: Option<String>,
+            symbol: Option<String>,
+            decimals: u8,
+        ) -> Self {
+            ink::utils::initialize_contract(|contract: &mut Self| {
+                Self::new_init(contract, initial_supply);
+                contract.name = name;
+                contract.symbol = symbol;
+                contract.decimals = decimals;
+            })
+        }
+
+        #[ink(constructor)]
+        pub fn new_capped(initial_supply: Balance, cap: Balance) -> Self {
+            assert!(initial_supply <= cap, "initial supply exceeds cap");
+            ink::utils::initialize_contract(|contract: &mut Self| {
+                Self::new_init(contract, initial_supply);
+                contract.cap = cap;
+            })
+        }
+
+        /// Like `new_with_metadata` without name and symbol (so also
+        /// uncapped), but takes the supply in whole tokens and scales it by
+        /// `10^decimals`. Panics if the scaled supply does not fit in `Balance`.
+        #[ink(constructor)]
+        pub fn new_whole_tokens(whole_supply: Balance, decimals: u8) -> Self {
+            let initial_supply = Balance::from(10u8)
+                .checked_pow(decimals.into())
+                .and_then(|unit| whole_supply.checked_mul(unit))
+                .expect("whole supply overflows after decimals scaling");
+            ink::utils::initialize_contract(|contract: &mut Self| {
+                Self::new_init(contract, initial_supply);
+                contract.decimals = decimals;
+            })
+        }
+
+        /// Deploys an uncapped `initial_supply` that optionally rejects the
+        /// all-zero account as a transfer recipient, spender or mint target.
+        #[ink(constructor)]
+        pub fn new_with_zero_address_check(initial_supply: Balance, reject_zero_address: bool) -> Self {
+            ink::utils::initialize_contract(|contract: &mut Self| {
+                Self::new_init(contract, initial_supply);
+                contract.reject_zero_address = reject_zero_address;
+            })
+        }
+
+        /// Deploys an uncapped `initial_supply` and lets anyone `claim`
+        /// `faucet_amount` fresh tokens at most once every `faucet_cooldown`
+        /// milliseconds.
+        #[ink(constructor)]
+        pub fn new_with_faucet(initial_supply: Balance, faucet_amount: Balance, faucet_cooldown: u64) -> Self {
+            ink::utils::initialize_contract(|contract: &mut Self| {
+                Self::new_init(contract, initial_supply);
+                contract.faucet_amount = faucet_amount;
+                contract.faucet_cooldown = faucet_cooldown;
+            })
+        }
+
+        /// Deploys an uncapped `initial_supply`. With `use_zero_for_mint` the
+        /// `Transfer` events of mints carry the all-zero account as `from`
+        /// instead of `None`, for indexers that expect a concrete sender.
+        #[ink(constructor)]
+        pub fn new_with_mint_sender(initial_supply: Balance, use_zero_for_mint: bool) -> Self {
+            ink::utils::initialize_contract(|contract: &mut Self| {
+                contract.use_zero_for_mint = use_zero_for_mint;
+                Self::new_init(contract, initial_supply);
+            })
+        }
+
+        /// Deploys an uncapped `initial_supply` and enables the recovery
+        /// messages for `recovery_authority` (ideally a multisig); every other
+        /// constructor leaves them disabled. Panics if it is the deployer.
         #[ink(constructor)]
-        pub fn new(initial_supply: u32) -> Self {
+        pub fn new_with_recovery_authority(initial_supply: Balance, recovery_authority: AccountId) -> Self {
+            assert!(
+                recovery_authority != Self::env().caller(),
+                "recovery authority must differ from the owner"
+            );
             ink::utils::initialize_contract(|contract: &mut Self| {
-                Self::new_init(contract, initial_supply)
+                Self::new_init(contract, initial_supply);
+                contract.recovery_authority = recovery_authority;
             })
         }
 
-        pub fn new_init(&mut self, initial_supply: u32) {
+        fn new_init(&mut self, initial_supply: Balance) {
             let caller = Self::env().caller();
+            self.owner = caller;
+            self.decimals = 18;
+            self.cap = Balance::MAX;
+            self.storage_version = STORAGE_VERSION;
+            self.allow_zero_transfers = true;
             self.total_supply = initial_supply;
-            self.balances.insert(&caller, &initial_supply);
+            self.total_minted = initial_supply;
+            if initial_supply == 0 {
+                return;
+            }
+
+            self.set_balance(&caller, initial_supply)
+                .expect("no dividends are accrued at deployment");
             self.env().emit_event(Transfer {
-                from: None,
+                from: self.mint_sender(),
                 to: Some(caller),
                 value: initial_supply,
+                value_bucket: value_bucket(initial_supply),
             })
         }
 
+        /// Describes the error whose SCALE-encoded discriminant is `code`, or
+        /// returns `None` if no such error exists.
         #[ink(message)]
-        pub fn total_supply(&self) -> u32 {
-            self.total_supply
+        pub fn describe_error(&self, code: u8) -> Option<String> {
+            <Error as scale::Decode>::decode(&mut &[code][..])
+                .ok()
+                .map(|error| String::from(error.message()))
         }
 
         #[ink(message)]
-        pub fn balance_of(&self, owner: AccountId) -> u32 {
-            self.balances.get(&owner).unwrap_or_default()
+        pub fn token_name(&self) -> Option<String> {
+            self.name.clone()
         }
 
-        pub fn transfer(&mut self, to: AccountId, value: u32) -> Result<()> {
-            let from = self.env().caller();
-            self.transfer_from_to(&from, &to, value)
+        #[ink(message)]
+        pub fn token_symbol(&self) -> Option<String> {
+            self.symbol.clone()
         }
 
-        fn transfer_from_to(&mut self, from: &AccountId, to: &AccountId, value: u32) -> Result<()> {
-            let from_balance = self.balance_of_impl(from);
-            if from_balance < value {
-                return Err(Error::InsufficientBalance);
-            } else {
-                self.balances.insert(from, &(from_balance - value));
-                let to_balance = self.balance_of_impl(to);
-                self.balances.insert(to, &(to_balance + value));
-                self.env().emit_event(Transfer {
-                    from: Some(*from),
-                    to: Some(*to),
-                    value,
-                });
+        #[ink(message)]
+        pub fn token_decimals(&self) -> u8 {
+            self.decimals
+        }
 
-                Ok(())
-            }
+        /// Always `true`: `decimals` is fixed at deployment and has no setter.
+        #[ink(message)]
+        pub fn decimals_locked(&self) -> bool {
+            true
         }
 
-        #[inline]
-        fn balance_of_impl(&self, owner: &AccountId) -> u32 {
-            self.balances.get(owner).unwrap_or_default()
+        /// Name, symbol, decimals and total supply in a single call.
+        #[ink(message)]
+        pub fn metadata(&self) -> (Option<String>, Option<String>, u8, Balance) {
+            (self.name.clone(), self.symbol.clone(), self.decimals, self.total_supply)
         }
 
         #[ink(message)]
-        pub fn approve(&mut self, spender: AccountId, value: u32) -> Result<()> {
-            let owner = self.env().caller();
-            self.allowances.insert(&(owner, spender), &value);
-            self.env().emit_event(Approval {
-                owner,
-                spender,
-                value,
-            });
+        pub fn total_minted(&self) -> Balance {
+            self.total_minted
+        }
 
-            Ok(())
+        #[ink(message)]
+        pub fn total_burned(&self) -> Balance {
+            self.total_burned
         }
 
         #[ink(message)]
-        pub fn allowance(&self, owner: AccountId, spender: AccountId) -> u32 {
-            self.allowance_impl(&owner, &spender)
+        pub fn cap(&self) -> Balance {
+            self.cap
         }
 
-        #[inline]
-        fn allowance_impl(&self, owner: &AccountId, spender: &AccountId) -> u32 {
-            self.allowances.get((owner, spender)).unwrap_or_default()
+        #[ink(message)]
+        pub fn total_supply(&self) -> Balance {
+            self.total_supply
         }
 
+        /// Splits `total_supply` into whole tokens and the remaining base
+        /// units, for display.
         #[ink(message)]
-        pub fn transfer_from(&mut self, from: AccountId, to: AccountId, value: u32) -> Result<()> {
-            let caller = self.env().caller();
-            let allowance = self.allowance_impl(&from, &caller);
-            if allowance < value {
-                return Err(Error::InsufficientAllowance);
-            } else {
-                self.transfer_from_to(&from, &to, value)?;
-                self.allowances.insert(&(from, caller), &(allowance - value));
-                Ok(())
+        pub fn total_supply_whole(&self) -> (Balance, Balance) {
+            match self.unit() {
+                Ok(unit) => (self.total_supply / unit, self.total_supply % unit),
+                // No whole token fits in `Balance` at this many decimals.
+                Err(_) => (0, self.total_supply),
             }
         }
-    }
 
+        /// Total supply minus the balances of accounts excluded from
+        /// circulation (treasury, locked vaults, burn address).
+        #[ink(message)]
+        pub fn circulating_supply(&self) -> Balance {
+            let excluded: Balance = (0..self.excluded_count)
+                .filter_map(|index| self.excluded_accounts.get(index))
+                .map(|account| self.balance_of_impl(&account))
+                .sum();
+            self.total_supply.saturating_sub(excluded)
+        }
 
-    #[cfg(test)]
-    mod tests {
-        use super::*;
-        use ink_lang as ink;
+        #[ink(message)]
+        pub fn exclude_from_circulation(&mut self, account: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            let old = self.excluded.contains(account);
+            if !old {
+                let count = self.excluded_count.checked_add(1).ok_or(Error::Overflow)?;
+                self.excluded.insert(account, &self.excluded_count);
+                self.excluded_accounts.insert(self.excluded_count, &account);
+                self.excluded_count = count;
+            }
+            self.config_changed(b"excluded", old.into(), 1);
+            Ok(())
+        }
 
-        #[ink::test]
-        fn default_works() {
-            let contract = Token::new(4294967000);
-            assert_eq!(contract.total_supply(), 4294967000);
+        #[ink(message)]
+        pub fn include_in_circulation(&mut self, account: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            let old = self.excluded.get(account);
+            if let Some(index) = old {
+                // Swap-remove: the last excluded account takes the freed slot.
+                let last = self.excluded_count - 1;
+                if let Some(moved) = self.excluded_accounts.get(last).filter(|_| index != last) {
+                    self.excluded_accounts.insert(index, &moved);
+                    self.excluded.insert(moved, &index);
+                }
+                self.excluded_accounts.remove(last);
+                self.excluded.remove(account);
+                self.excluded_count = last;
+            }
+            self.config_changed(b"excluded", old.is_some().into(), 0);
+            Ok(())
         }
 
-        #[ink::test]
-        fn balance_works() {
-            let contract = Token::new(4294967000);
-            assert_eq!(contract.balance_of(AccountId::from([0x1; 32])), 4294967000);
-            assert_eq!(contract.balance_of(AccountId::from([0x0; 32])), 0);
+        #[ink(message)]
+        pub fn balance_of(&self, owner: AccountId) -> Balance {
+            self.balances.get(owner).unwrap_or_default()
         }
 
-        #[ink::test]
-        fn transfer_works() {
-            let mut contract = Token::new(4294967000);
-            assert_eq!(contract.balance_of(AccountId::from([0x0; 32])), 0);
-            assert_eq!(contract.transfer(AccountId::from([0x0; 32]), 4294967000), Ok(()));
-            assert_eq!(contract.balance_of(AccountId::from([0x0; 32])), 4294967000);
+        #[ink(message)]
+        pub fn balances_of(&self, owners: Vec<AccountId>) -> Vec<Balance> {
+            owners.iter().map(|owner| self.balance_of_impl(owner)).collect()
         }
 
-        #[ink::test]
-        fn transfer_from_works() {
-            let mut contract = Token::new(4294967000);
-            assert_eq!(contract.balance_of(AccountId::from([0x1; 32])), 4294967000);
-            contract.approve(AccountId::from([0x1; 32]), 1000000).unwrap();
-            contract.transfer_from(AccountId::from([0x1; 32]), AccountId::from([0x0; 32]), 69).unwrap();
-            assert_eq!(contract.balance_of(AccountId::from([0x0; 32])), 69);
+        /// Tokens held by the contract's own account, including custody.
+        #[ink(message)]
+        pub fn self_balance(&self) -> Balance {
+            self.balance_of_impl(&self.env().account_id())
         }
 
-        #[ink::test]
-        fn allowance_works() {
-            let mut contract = Token::new(4294967000);
-            assert_eq!(contract.balance_of(AccountId::from([0x1; 32])), 4294967000);
-            contract.approve(AccountId::from([0x1; 32]), 1000000).unwrap();
-            assert_eq!(contract.allowance(AccountId::from([0x1; 32]), AccountId::from([0x1; 32])), 1000000);
+        /// Returns `(balance, is_frozen, locked_amount)` for `account`, where
+        /// `locked_amount` only counts a lock that has not expired yet.
+        #[ink(message)]
+        pub fn account_info(&self, account: AccountId) -> (Balance, bool, Balance) {
+            (
+                self.balance_of_impl(&account),
+                self.frozen.contains(account),
+                self.locked_balance_of(&account),
+            )
+        }
+
+        #[ink(message)]
+        pub fn transfer(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            let from = self.env().caller();
+            self.transfer_from_to(&from, &to, value)
+        }
+
+        /// Transfers the caller's entire balance to `to`.
+        #[ink(message)]
+        pub fn transfer_all(&mut self, to: AccountId) -> Result<()> {
+            let from = self.env().caller();
+            let balance = self.balance_of_impl(&from);
+            if balance == 0 {
+                return Err(Error::InsufficientBalance);
+            }
+
+            self.transfer_from_to(&from, &to, balance)
+        }
+
+        /// Transfers `whole` tokens plus `fractional` base units, i.e.
+        /// `whole * 10^decimals + fractional`.
+        #[ink(message)]
+        pub fn transfer_units(&mut self, to: AccountId, whole: Balance, fractional: Balance) -> Result<()> {
+            let unit = self.unit()?;
+            if fractional >= unit {
+                return Err(Error::InvalidFraction);
+            }
+
+            let value = whole
+                .checked_mul(unit)
+                .and_then(|value| value.checked_add(fractional))
+                .ok_or(Error::Overflow)?;
+            self.transfer(to, value)
+        }
+
+        /// Returns the number of base units in one whole token.
+        fn unit(&self) -> Result<Balance> {
+            Balance::from(10u8)
+                .checked_pow(self.decimals.into())
+                .ok_or(Error::Overflow)
+        }
+
+        #[ink(message)]
+        pub fn batch_transfer(&mut self, recipients: Vec<AccountId>, amounts: Vec<Balance>) -> Result<()> {
+            if recipients.len() != amounts.len() {
+                return Err(Error::LengthMismatch);
+            }
+
+            let from = self.env().caller();
+            let total = amounts
+                .iter()
+                .try_fold(0, |sum: Balance, amount| sum.checked_add(*amount))
+                .ok_or(Error::Overflow)?;
+            if self.balance_of_impl(&from) < total {
+                return Err(Error::InsufficientBalance);
+            }
+
+            if !self.batch_events {
+                for (to, value) in recipients.iter().zip(amounts) {
+                    self.transfer_from_to(&from, to, value)?;
+                }
+                return Ok(());
+            }
+
+            self.batching = true;
+            let result = recipients
+                .iter()
+                .zip(&amounts)
+                .map(|(to, value)| {
+                    let (fee, burn) = self.check_transfer(&from, to, *value)?;
+                    self.transfer_from_to(&from, to, *value)?;
+                    net_of(*value, fee, burn)
+                })
+                .collect::<Result<Vec<_>>>();
+            self.batching = false;
+            self.env().emit_event(TransferBatch {
+                from,
+                to_list: recipients,
+                values: result?,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn batch_events(&self) -> bool {
+            self.batch_events
+        }
+
+        /// When enabled, `batch_transfer` emits one `TransferBatch` with the
+        /// amounts each recipient received, net of fees, in place of the
+        /// per-recipient `Transfer` events. Fee, tax and burn legs keep their
+        /// own `Transfer` events, so the events still add up to the balances.
+        #[ink(message)]
+        pub fn set_batch_events(&mut self, batch_events: bool) -> Result<()> {
+            self.ensure_owner()?;
+            let old = self.batch_events.into();
+            self.batch_events = batch_events;
+            self.config_changed(b"batchevt", old, batch_events.into());
+            Ok(())
+        }
+
+        fn transfer_from_to(&mut self, from: &AccountId, to: &AccountId, value: Balance) -> Result<()> {
+            self.with_guard(|contract| contract.transfer_from_to_unguarded(from, to, value))
+        }
+
+        /// Transfers `value` to `to` and, if `to` is a contract, calls its
+        /// `on_received` with `data`. The whole transfer is rejected if the
+        /// receiving contract reverts.
+        #[ink(message)]
+        pub fn transfer_with_data(&mut self, to: AccountId, value: Balance, data: Vec<u8>) -> Result<()> {
+            let from = self.env().caller();
+            self.with_guard(|contract| {
+                contract.transfer_from_to_unguarded(&from, &to, value)?;
+                if contract.is_contract_account(&to) {
+                    contract.notify_received(&from, &to, value, data)?;
+                }
+                Ok(())
+            })
+        }
+
+        fn notify_received(&self, from: &AccountId, to: &AccountId, value: Balance, data: Vec<u8>) -> Result<()> {
+            use ink_env::call::{
+                build_call,
+                Call,
+                ExecutionInput,
+                Selector,
+            };
+
+            build_call::<Environment>()
+                .call_type(Call::new().callee(*to))
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ON_RECEIVED_SELECTOR))
+                        .push_arg(self.env().caller())
+                        .push_arg(from)
+                        .push_arg(value)
+                        .push_arg(data),
+                )
+                .returns::<()>()
+                .fire()
+                .map_err(|_| Error::TransferRejected)
+        }
+
+        /// Returns whether `account` has contract code deployed.
+        ///
+        /// The off-chain test engine cannot answer this, so unit tests treat
+        /// every account as a plain (non-contract) account.
+        fn is_contract_account(&self, account: &AccountId) -> bool {
+            #[cfg(not(test))]
+            {
+                self.env().is_contract(account)
+            }
+            #[cfg(test)]
+            {
+                let _ = account;
+                false
+            }
+        }
+
+        /// Runs `f` while holding the reentrancy lock, so that a nested call
+        /// back into a guarded path fails with `Error::Reentrancy`.
+        fn with_guard<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+            if self.reentrancy_lock.contains(()) {
+                return Err(Error::Reentrancy);
+            }
+
+            self.reentrancy_lock.insert((), &());
+            let result = f(self);
+            self.reentrancy_lock.remove(());
+            result
+        }
+
+        fn transfer_from_to_unguarded(&mut self, from: &AccountId, to: &AccountId, value: Balance) -> Result<()> {
+            let (fee, burn) = self.check_transfer(from, to, value)?;
+            if self.max_transfers_per_block > 0 {
+                let key = (*from, self.env().block_number());
+                let transfers = self.transfers_this_block.get(key).unwrap_or_default();
+                self.transfers_this_block.insert(key, &transfers.saturating_add(1));
+            }
+            if self.trade_cooldown > 0 && *from != self.owner {
+                self.last_trade.insert(from, &self.env().block_timestamp());
+            }
+
+            if from == to {
+                if !self.batching {
+                    self.emit_transfer(from, to, value);
+                }
+                return Ok(());
+            }
+
+            let new_from_balance = self
+                .balance_of_impl(from)
+                .checked_sub(value)
+                .ok_or(Error::InsufficientBalance)?;
+            self.set_balance(from, new_from_balance)?;
+            let net = net_of(value, fee, burn)?;
+            if self.batching {
+                // Reported by the `TransferBatch` event instead.
+                self.add_balance(to, net)?;
+            } else {
+                self.credit(from, to, net)?;
+            }
+            if fee > 0 {
+                let collector = self.fee_collector;
+                self.credit(from, &collector, fee)?;
+            }
+            if burn > 0 {
+                self.destroy(from, burn, [0; 32])?;
+            }
+
+            Ok(())
+        }
+
+        /// Checks every transfer policy for moving `value` from `from` to
+        /// `to` without touching storage, and returns the fee and the burn
+        /// it would take.
+        fn check_transfer(&self, from: &AccountId, to: &AccountId, value: Balance) -> Result<(Balance, Balance)> {
+            if self.transfers_paused {
+                return Err(Error::Paused);
+            }
+            self.ensure_not_zero_address(to)?;
+            if self.frozen.contains(from) || self.frozen.contains(to) {
+                return Err(Error::AccountFrozen);
+            }
+            if self.whitelist_only && !self.is_whitelisted_transfer(from, to) {
+                return Err(Error::NotWhitelisted);
+            }
+
+            if let Some(max_tx_amount) = self.max_tx_amount {
+                if value > max_tx_amount && !self.is_privileged(from) {
+                    return Err(Error::ExceedsMaxTx);
+                }
+            }
+            if value == 0 && !self.allow_zero_transfers {
+                return Err(Error::ZeroAmount);
+            }
+            if value != 0 && value < self.min_transfer {
+                return Err(Error::BelowMinimum);
+            }
+            if self.max_transfers_per_block > 0 {
+                let key = (*from, self.env().block_number());
+                if self.transfers_this_block.get(key).unwrap_or_default() >= self.max_transfers_per_block {
+                    return Err(Error::RateLimited);
+                }
+            }
+            if self.trade_cooldown > 0 && *from != self.owner {
+                if let Some(last_trade) = self.last_trade.get(from) {
+                    if self.env().block_timestamp().saturating_sub(last_trade) < self.trade_cooldown {
+                        return Err(Error::CooldownActive);
+                    }
+                }
+            }
+
+            if from == to {
+                if self.balance_of_impl(from) < value {
+                    return Err(Error::InsufficientBalance);
+                }
+                return Ok((0, 0));
+            }
+
+            let new_from_balance = self
+                .balance_of_impl(from)
+                .checked_sub(value)
+                .ok_or(Error::InsufficientBalance)?;
+            if new_from_balance < self.locked_balance_of(from) {
+                return Err(Error::TokensLocked);
+            }
+            let (tax_fee, tax_burn) = self.tax_for(value)?;
+            let fee = self.fee_for(value)?.checked_add(tax_fee).ok_or(Error::Overflow)?;
+            let burn = if *from == self.owner || *to == self.owner {
+                0
+            } else {
+                self.burn_for(value)?
+            };
+            let burn = burn.checked_add(tax_burn).ok_or(Error::Overflow)?;
+            // Rounding up may take more than `value` from a tiny transfer.
+            let fee = fee.min(value);
+            let burn = burn.min(value - fee);
+            let new_to_balance = self
+                .balance_of_impl(to)
+                .checked_add(net_of(value, fee, burn)?)
+                .ok_or(Error::Overflow)?;
+            if let Some(max_wallet) = self.max_wallet {
+                let exempt = *to == self.owner || *to == self.fee_collector;
+                if !exempt && new_to_balance > max_wallet {
+                    return Err(Error::ExceedsMaxWallet);
+                }
+            }
+
+            Ok((fee, burn))
+        }
+
+        /// Returns what `to` would receive if `from` transferred `value` now,
+        /// after fees, or the error the transfer would fail with.
+        #[ink(message)]
+        pub fn simulate_transfer(&self, from: AccountId, to: AccountId, value: Balance) -> Result<Balance> {
+            if self.reentrancy_lock.contains(()) {
+                return Err(Error::Reentrancy);
+            }
+
+            let (fee, burn) = self.check_transfer(&from, &to, value)?;
+            net_of(value, fee, burn)
+        }
+
+        /// Moves `value` from `from` to `to` without applying any transfer
+        /// policy (pause, freezes, locks or fees).
+        fn move_tokens(&mut self, from: &AccountId, to: &AccountId, value: Balance) -> Result<()> {
+            let new_from_balance = self
+                .balance_of_impl(from)
+                .checked_sub(value)
+                .ok_or(Error::InsufficientBalance)?;
+            self.set_balance(from, new_from_balance)?;
+            self.credit(from, to, value)
+        }
+
+        /// Adds `value` already debited from `from` to `to`'s balance.
+        fn credit(&mut self, from: &AccountId, to: &AccountId, value: Balance) -> Result<()> {
+            self.add_balance(to, value)?;
+            self.emit_transfer(from, to, value);
+            Ok(())
+        }
+
+        fn add_balance(&mut self, to: &AccountId, value: Balance) -> Result<()> {
+            let new_to_balance = self
+                .balance_of_impl(to)
+                .checked_add(value)
+                .ok_or(Error::Overflow)?;
+            self.set_balance(to, new_to_balance)?;
+            Ok(())
+        }
+
+        fn emit_transfer(&self, from: &AccountId, to: &AccountId, value: Balance) {
+            self.env().emit_event(Transfer {
+                from: Some(*from),
+                to: Some(*to),
+                value,
+                value_bucket: value_bucket(value),
+            });
+        }
+
+        /// Moves `total` from the owner into the contract's custody, to be
+        /// released to `beneficiary` linearly over `duration` from `start_ts`.
+        #[ink(message)]
+        pub fn create_vesting(
+            &mut self,
+            beneficiary: AccountId,
+            total: Balance,
+            start_ts: u64,
+            duration: u64,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            if self.vesting.contains(beneficiary) {
+                return Err(Error::VestingAlreadyExists);
+            }
+
+            let owner = self.owner;
+            self.escrow(&owner, total)?;
+            self.vesting.insert(
+                beneficiary,
+                &VestingSchedule {
+                    total,
+                    start_ts,
+                    duration,
+                    released: 0,
+                },
+            );
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn vesting_of(&self, beneficiary: AccountId) -> Option<VestingSchedule> {
+            self.vesting.get(beneficiary)
+        }
+
+        #[ink(message)]
+        pub fn release(&mut self) -> Result<()> {
+            let beneficiary = self.env().caller();
+            let mut schedule = self.vesting.get(beneficiary).ok_or(Error::NothingToRelease)?;
+            let amount = schedule
+                .vested_at(self.env().block_timestamp())?
+                .checked_sub(schedule.released)
+                .ok_or(Error::Underflow)?;
+            if amount == 0 {
+                return Err(Error::NothingToRelease);
+            }
+
+            self.release_escrow(&beneficiary, amount)?;
+            schedule.released = schedule.released.checked_add(amount).ok_or(Error::Overflow)?;
+            self.vesting.insert(beneficiary, &schedule);
+            self.env().emit_event(TokensReleased { beneficiary, amount });
+
+            Ok(())
+        }
+
+        /// Returns `account`'s staked principal and when it was staked.
+        #[ink(message)]
+        pub fn staked_of(&self, account: AccountId) -> Option<(Balance, u64)> {
+            self.staked.get(account)
+        }
+
+        #[ink(message)]
+        pub fn owed_stake_reward(&self, account: AccountId) -> Balance {
+            self.owed_stake_rewards.get(account).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn stake_reward_bps(&self) -> u16 {
+            self.stake_reward_bps
+        }
+
+        /// Sets the staking reward, in basis points of principal per
+        /// `STAKE_REWARD_PERIOD`.
+        #[ink(message)]
+        pub fn set_stake_reward_bps(&mut self, stake_reward_bps: u16) -> Result<()> {
+            self.ensure_owner()?;
+            let old = self.stake_reward_bps.into();
+            self.stake_reward_bps = stake_reward_bps;
+            self.config_changed(b"stk_bps", old, stake_reward_bps.into());
+            Ok(())
+        }
+
+        /// Moves `amount` from the caller into the contract's custody. Staking
+        /// again first pays out the reward accrued so far and restarts the clock
+        /// for the combined principal.
+        #[ink(message)]
+        pub fn stake(&mut self, amount: Balance) -> Result<()> {
+            let caller = self.env().caller();
+            let balance = self.balance_of_impl(&caller);
+            if balance < amount {
+                return Err(Error::InsufficientBalance);
+            }
+            if balance.saturating_sub(amount) < self.locked_balance_of(&caller) {
+                return Err(Error::TokensLocked);
+            }
+
+            let mut principal = 0;
+            if let Some((staked, since)) = self.staked.get(caller) {
+                let reward = self.stake_reward(staked, since)?;
+                self.pay_stake_reward(caller, reward)?;
+                principal = staked;
+            }
+            let principal = principal.checked_add(amount).ok_or(Error::Overflow)?;
+            self.escrow(&caller, amount)?;
+            self.staked.insert(caller, &(principal, self.env().block_timestamp()));
+            Ok(())
+        }
+
+        /// Returns the caller's principal plus the reward minted for the time
+        /// it was staked. While minting is paused the principal is still
+        /// returned and the reward is owed until `claim_stake_reward`.
+        #[ink(message)]
+        pub fn unstake(&mut self) -> Result<()> {
+            let caller = self.env().caller();
+            let (principal, since) = self.staked.get(caller).ok_or(Error::NothingStaked)?;
+            let reward = self.stake_reward(principal, since)?;
+            self.staked.remove(caller);
+            self.release_escrow(&caller, principal)?;
+            self.pay_stake_reward(caller, reward)
+        }
+
+        /// Mints the staking reward owed to the caller since minting was
+        /// paused.
+        #[ink(message)]
+        pub fn claim_stake_reward(&mut self) -> Result<()> {
+            let caller = self.env().caller();
+            let owed = self.owed_stake_rewards.get(caller).ok_or(Error::NothingOwed)?;
+            self.mint_impl(caller, owed)?;
+            self.owed_stake_rewards.remove(caller);
+            Ok(())
+        }
+
+        /// Mints `reward` to `account`, or records it as owed while minting is
+        /// paused.
+        fn pay_stake_reward(&mut self, account: AccountId, reward: Balance) -> Result<()> {
+            if reward == 0 {
+                return Ok(());
+            }
+            if !self.mint_paused {
+                return self.mint_impl(account, reward);
+            }
+
+            let owed = self.owed_stake_reward(account).checked_add(reward).ok_or(Error::Overflow)?;
+            self.owed_stake_rewards.insert(account, &owed);
+            Ok(())
+        }
+
+        /// Reward for `principal` staked since `since`, limited by
+        /// `MAX_STAKE_REWARD_BPS` and by the room left under the cap.
+        fn stake_reward(&self, principal: Balance, since: u64) -> Result<Balance> {
+            let elapsed = self.env().block_timestamp().saturating_sub(since);
+            let reward = principal
+                .checked_mul(Balance::from(self.stake_reward_bps))
+                .and_then(|reward| reward.checked_mul(Balance::from(elapsed)))
+                .ok_or(Error::Overflow)?
+                / (10_000 * Balance::from(STAKE_REWARD_PERIOD));
+            let max_reward = principal.checked_mul(MAX_STAKE_REWARD_BPS).ok_or(Error::Overflow)? / 10_000;
+            Ok(reward.min(max_reward).min(self.cap.saturating_sub(self.total_supply)))
+        }
+
+        fn config_bytes_changed(&self, key: &[u8], old: [u8; 32], new: [u8; 32]) {
+            let mut padded = [0u8; 8];
+            padded[..key.len()].copy_from_slice(key);
+            self.env().emit_event(ConfigBytesChanged {
+                key: padded,
+                old,
+                new,
+                by: self.env().caller(),
+            });
+        }
+
+        fn config_changed(&self, key: &[u8], old: u64, new: u64) {
+            let mut padded = [0u8; 8];
+            padded[..key.len()].copy_from_slice(key);
+            self.env().emit_event(ConfigChanged {
+                key: padded,
+                old,
+                new,
+                by: self.env().caller(),
+            });
+        }
+
+        /// Returns whether `account` is the owner or a minter, which exempts
+        /// it from the anti-whale limits.
+        fn is_privileged(&self, account: &AccountId) -> bool {
+            *account == self.owner || self.minters.contains(account)
+        }
+
+        #[ink(message)]
+        pub fn max_tx_amount(&self) -> Option<Balance> {
+            self.max_tx_amount
+        }
+
+        #[ink(message)]
+        pub fn set_max_tx_amount(&mut self, max_tx_amount: Option<Balance>) -> Result<()> {
+            self.ensure_owner()?;
+            let old = limit_value(self.max_tx_amount);
+            self.max_tx_amount = max_tx_amount;
+            self.config_changed(b"max_tx", old, limit_value(max_tx_amount));
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn max_transfers_per_block(&self) -> u32 {
+            self.max_transfers_per_block
+        }
+
+        /// Limits how many transfers an account may send per block; zero
+        /// disables the limit.
+        #[ink(message)]
+        pub fn set_max_transfers_per_block(&mut self, max_transfers_per_block: u32) -> Result<()> {
+            self.ensure_owner()?;
+            let old = self.max_transfers_per_block.into();
+            self.max_transfers_per_block = max_transfers_per_block;
+            self.config_changed(b"tx_block", old, max_transfers_per_block.into());
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn trade_cooldown(&self) -> u64 {
+            self.trade_cooldown
+        }
+
+        /// Minimum time, in milliseconds, between two transfers sent by the
+        /// same account; the owner is exempt and zero disables the check.
+        #[ink(message)]
+        pub fn set_trade_cooldown(&mut self, trade_cooldown: u64) -> Result<()> {
+            self.ensure_owner()?;
+            let old = self.trade_cooldown;
+            self.trade_cooldown = trade_cooldown;
+            self.config_changed(b"cooldown", old, trade_cooldown);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn allow_zero_transfers(&self) -> bool {
+            self.allow_zero_transfers
+        }
+
+        /// Zero-value transfers are allowed, and emit `Transfer`, by default.
+        #[ink(message)]
+        pub fn set_allow_zero_transfers(&mut self, allow_zero_transfers: bool) -> Result<()> {
+            self.ensure_owner()?;
+            let old = self.allow_zero_transfers.into();
+            self.allow_zero_transfers = allow_zero_transfers;
+            self.config_changed(b"zero_tx", old, allow_zero_transfers.into());
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn min_transfer(&self) -> Balance {
+            self.min_transfer
+        }
+
+        /// Smallest non-zero amount a transfer may move; zero disables the check.
+        #[ink(message)]
+        pub fn set_min_transfer(&mut self, min_transfer: Balance) -> Result<()> {
+            self.ensure_owner()?;
+            let old = saturate(self.min_transfer);
+            self.min_transfer = min_transfer;
+            self.config_changed(b"min_tx", old, saturate(min_transfer));
+            Ok(())
+        }
+
+        /// Moves `value` from `from` into the contract's custody.
+        fn escrow(&mut self, from: &AccountId, value: Balance) -> Result<()> {
+            let escrowed = self.escrowed.checked_add(value).ok_or(Error::Overflow)?;
+            self.move_tokens(from, &self.env().account_id(), value)?;
+            self.escrowed = escrowed;
+            Ok(())
+        }
+
+        /// Pays `value` held in the contract's custody out to `to`.
+        fn release_escrow(&mut self, to: &AccountId, value: Balance) -> Result<()> {
+            let escrowed = self.escrowed.checked_sub(value).ok_or(Error::Underflow)?;
+            self.move_tokens(&self.env().account_id(), to, value)?;
+            self.escrowed = escrowed;
+            Ok(())
+        }
+
+        /// Sends the tokens held by the contract's own account to `to`, for
+        /// example tokens that users transferred to the contract by mistake.
+        /// Tokens held in custody for vesting, dividends or staking are left
+        /// untouched, so this fails with `InsufficientBalance` unless the
+        /// contract holds more than it has in custody.
+        #[ink(message)]
+        pub fn rescue_self_balance(&mut self, to: AccountId) -> Result<()> {
+            self.ensure_recovery_authority()?;
+            let contract = self.env().account_id();
+            let balance = self.balance_of_impl(&contract).saturating_sub(self.escrowed);
+            if balance == 0 {
+                return Err(Error::InsufficientBalance);
+            }
+
+            self.move_tokens(&contract, &to, balance)
+        }
+
+        /// Sends `amount` of the foreign PSP22 `token` held by this contract
+        /// to `to`, for example tokens that users sent here by mistake.
+        #[ink(message)]
+        pub fn recover_foreign_token(&mut self, token: AccountId, to: AccountId, amount: Balance) -> Result<()> {
+            use ink_env::call::{
+                build_call,
+                Call,
+                ExecutionInput,
+                Selector,
+            };
+
+            self.ensure_recovery_authority()?;
+            if !self.is_contract_account(&token) {
+                return Err(Error::CallFailed);
+            }
+
+            build_call::<Environment>()
+                .call_type(Call::new().callee(token))
+                .exec_input(
+                    ExecutionInput::new(Selector::new(PSP22_TRANSFER_SELECTOR))
+                        .push_arg(to)
+                        .push_arg(amount)
+                        .push_arg(Vec::<u8>::new()),
+                )
+                .returns::<core::result::Result<(), PSP22Error>>()
+                .fire()
+                .map_err(|_| Error::CallFailed)?
+                .map_err(|_| Error::CallFailed)
+        }
+
+        #[ink(message)]
+        pub fn max_wallet(&self) -> Option<Balance> {
+            self.max_wallet
+        }
+
+        #[ink(message)]
+        pub fn set_max_wallet(&mut self, max_wallet: Option<Balance>) -> Result<()> {
+            self.ensure_owner()?;
+            let old = limit_value(self.max_wallet);
+            self.max_wallet = max_wallet;
+            self.config_changed(b"max_wal", old, limit_value(max_wallet));
+            Ok(())
+        }
+
+        /// Prevents `account` from moving its balance below `amount` until
+        /// the block timestamp reaches `unlock_ts`. Replaces any earlier lock.
+        #[ink(message)]
+        pub fn set_lock(&mut self, account: AccountId, amount: Balance, unlock_ts: u64) -> Result<()> {
+            self.ensure_owner()?;
+            let old = self.locks.get(account).map_or(0, |(amount, _)| saturate(amount));
+            self.locks.insert(account, &(amount, unlock_ts));
+            self.config_changed(b"lock", old, saturate(amount));
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn lock_of(&self, account: AccountId) -> Option<(Balance, u64)> {
+            self.locks.get(account)
+        }
+
+        /// Returns the part of `owner`'s balance that is not locked.
+        #[ink(message)]
+        pub fn spendable_balance_of(&self, owner: AccountId) -> Balance {
+            self.balance_of_impl(&owner).saturating_sub(self.locked_balance_of(&owner))
+        }
+
+        /// Returns the part of `account`'s balance that is still locked.
+        fn locked_balance_of(&self, account: &AccountId) -> Balance {
+            match self.locks.get(account) {
+                Some((amount, unlock_ts)) if self.env().block_timestamp() < unlock_ts => amount,
+                _ => 0,
+            }
+        }
+
+        fn burn_for(&self, value: Balance) -> Result<Balance> {
+            self.bps_of(value, self.burn_rate_bps.into())
+        }
+
+        /// `bps` basis points of `value`, rounded as `round_fees_up` says.
+        fn bps_of(&self, value: Balance, bps: Balance) -> Result<Balance> {
+            let scaled = value.checked_mul(bps).ok_or(Error::Overflow)?;
+            if self.round_fees_up {
+                Ok(scaled.div_ceil(10_000))
+            } else {
+                Ok(scaled / 10_000)
+            }
+        }
+
+        #[ink(message)]
+        pub fn round_fees_up(&self) -> bool {
+            self.round_fees_up
+        }
+
+        /// Rounds fee, burn and tax amounts up instead of down. A transfer
+        /// never takes more than its own value.
+        #[ink(message)]
+        pub fn set_round_fees_up(&mut self, round_fees_up: bool) -> Result<()> {
+            self.ensure_owner()?;
+            let old = self.round_fees_up.into();
+            self.round_fees_up = round_fees_up;
+            self.config_changed(b"fee_ceil", old, round_fees_up.into());
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn burn_rate_bps(&self) -> u16 {
+            self.burn_rate_bps
+        }
+
+        /// Burns `burn_rate_bps` of every transfer that does not involve the
+        /// owner, on top of the transfer fee. Capped at `MAX_FEE_BPS`; see
+        /// `set_fee` for the timelock.
+        #[ink(message)]
+        pub fn set_burn_rate(&mut self, burn_rate_bps: u16) -> Result<()> {
+            self.ensure_owner()?;
+            if burn_rate_bps > MAX_FEE_BPS {
+                return Err(Error::FeeTooHigh);
+            }
+            self.ensure_rate_change(
+                self.fee_bps,
+                self.tax_bps,
+                burn_rate_bps,
+                false,
+                ACTION_SET_BURN_RATE,
+                &burn_rate_bps,
+            )?;
+
+            let old = self.burn_rate_bps.into();
+            self.burn_rate_bps = burn_rate_bps;
+            self.config_changed(b"burn_bps", old, burn_rate_bps.into());
+            Ok(())
+        }
+
+        /// Splits the `tax_bps` tax on `value` into the part sent to the fee
+        /// collector and the part burned.
+        fn tax_for(&self, value: Balance) -> Result<(Balance, Balance)> {
+            let tax = self.bps_of(value, self.tax_bps.into())?;
+            let burned = self.bps_of(tax, self.burn_share_bps.into())?;
+            Ok((tax - burned, burned))
+        }
+
+        #[ink(message)]
+        pub fn tax_bps(&self) -> u16 {
+            self.tax_bps
+        }
+
+        #[ink(message)]
+        pub fn burn_share_bps(&self) -> u16 {
+            self.burn_share_bps
+        }
+
+        /// Takes `tax_bps` of every transfer, on top of the fee and burn rate,
+        /// and burns `burn_share_bps` of it; the rest goes to the fee
+        /// collector. The tax is capped at `MAX_FEE_BPS`; see `set_fee` for
+        /// the timelock.
+        #[ink(message)]
+        pub fn set_tax(&mut self, tax_bps: u16, burn_share_bps: u16) -> Result<()> {
+            self.ensure_owner()?;
+            if tax_bps > MAX_FEE_BPS {
+                return Err(Error::FeeTooHigh);
+            }
+            if burn_share_bps > 10_000 {
+                return Err(Error::InvalidFraction);
+            }
+            self.ensure_rate_change(
+                self.fee_bps,
+                tax_bps,
+                self.burn_rate_bps,
+                burn_share_bps < self.burn_share_bps,
+                ACTION_SET_TAX,
+                &(tax_bps, burn_share_bps),
+            )?;
+
+            let old_tax = self.tax_bps.into();
+            let old_share = self.burn_share_bps.into();
+            self.tax_bps = tax_bps;
+            self.burn_share_bps = burn_share_bps;
+            self.config_changed(b"tax_bps", old_tax, tax_bps.into());
+            self.config_changed(b"tax_burn", old_share, burn_share_bps.into());
+            Ok(())
+        }
+
+        fn fee_for(&self, value: Balance) -> Result<Balance> {
+            self.bps_of(value, self.fee_bps.into())
+        }
+
+        #[ink(message)]
+        pub fn fee_bps(&self) -> u16 {
+            self.fee_bps
+        }
+
+        #[ink(message)]
+        pub fn fee_collector(&self) -> AccountId {
+            self.fee_collector
+        }
+
+        /// While the combined fee, tax and burn rate ends up above
+        /// `TIMELOCK_FEE_BPS`, raising it or redirecting more of it to the
+        /// collector needs an elapsed `ACTION_SET_FEE` queued for
+        /// `(fee_bps, collector)`; likewise for `set_tax` and `set_burn_rate`.
+        #[ink(message)]
+        pub fn set_fee(&mut self, fee_bps: u16, collector: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            if fee_bps > MAX_FEE_BPS {
+                return Err(Error::FeeTooHigh);
+            }
+            self.ensure_rate_change(
+                fee_bps,
+                self.tax_bps,
+                self.burn_rate_bps,
+                collector != self.fee_collector,
+                ACTION_SET_FEE,
+                &(fee_bps, collector),
+            )?;
+
+            let old_collector = self.fee_collector;
+            if collector != old_collector {
+                self.fee_collector = collector;
+                self.config_bytes_changed(b"fee_col", account_bytes(Some(old_collector)), account_bytes(Some(collector)));
+            }
+            let old = self.fee_bps.into();
+            self.fee_bps = fee_bps;
+            self.config_changed(b"fee_bps", old, fee_bps.into());
+            Ok(())
+        }
+
+        /// Consumes `action_id` queued for `params` if the new combined rate
+        /// is above `TIMELOCK_FEE_BPS` and either exceeds the current one or
+        /// `redirects` more of it to the fee collector.
+        fn ensure_rate_change(
+            &mut self,
+            fee_bps: u16,
+            tax_bps: u16,
+            burn_rate_bps: u16,
+            redirects: bool,
+            action_id: u32,
+            params: &impl scale::Encode,
+        ) -> Result<()> {
+            let combined = |fee: u16, tax: u16, burn: u16| u32::from(fee) + u32::from(tax) + u32::from(burn);
+            let old = combined(self.fee_bps, self.tax_bps, self.burn_rate_bps);
+            let new = combined(fee_bps, tax_bps, burn_rate_bps);
+            if new > TIMELOCK_FEE_BPS.into() && (new > old || redirects) {
+                self.consume_action(action_id, params)?;
+            }
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn mint(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            self.mint_as_caller(to, value)
+        }
+
+        /// Mints on behalf of the caller, which must be the owner or a minter;
+        /// a minter's issuance is charged against its `minter_cap`.
+        fn mint_as_caller(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            let caller = self.env().caller();
+            if caller != self.owner && !self.minters.contains(caller) {
+                return Err(Error::NotMinter);
+            }
+            if caller == self.owner {
+                return self.mint_impl(to, value);
+            }
+
+            let minted = self
+                .minter_minted
+                .get(caller)
+                .unwrap_or_default()
+                .checked_add(value)
+                .ok_or(Error::Overflow)?;
+            if let Some(cap) = self.minter_cap.get(caller) {
+                if minted > cap {
+                    return Err(Error::MinterCapExceeded);
+                }
+            }
+            self.mint_impl(to, value)?;
+            self.minter_minted.insert(caller, &minted);
+            Ok(())
+        }
+
+        /// Returns `(cap, minted)` for `minter`; no cap means unlimited.
+        #[ink(message)]
+        pub fn minter_allowance(&self, minter: AccountId) -> (Option<Balance>, Balance) {
+            (
+                self.minter_cap.get(minter),
+                self.minter_minted.get(minter).unwrap_or_default(),
+            )
+        }
+
+        /// Limits the total `minter` may ever issue through `mint` and
+        /// `bridge_unlock`; `None`
+        /// lifts the limit. What it already minted keeps counting.
+        #[ink(message)]
+        pub fn set_minter_cap(&mut self, minter: AccountId, cap: Option<Balance>) -> Result<()> {
+            self.ensure_owner()?;
+            let old = limit_value(self.minter_cap.get(minter));
+            match cap {
+                Some(cap) => self.minter_cap.insert(minter, &cap),
+                None => self.minter_cap.remove(minter),
+            }
+            self.config_changed(b"mint_cap", old, limit_value(cap));
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn authorizer(&self) -> Option<AccountId> {
+            self.authorizer
+        }
+
+        /// Sets the ECDSA account whose signatures `mint_with_sig` accepts.
+        #[ink(message)]
+        pub fn set_authorizer(&mut self, authorizer: Option<AccountId>) -> Result<()> {
+            self.ensure_owner()?;
+            let old = account_bytes(self.authorizer);
+            self.authorizer = authorizer;
+            self.config_bytes_changed(b"authrzr", old, account_bytes(authorizer));
+            Ok(())
+        }
+
+        /// Mints `value` to `to` on the authority of an ECDSA signature by
+        /// `authorizer` over `mint_hash(to, value, nonce)`. Each nonce can be
+        /// used once.
+        #[ink(message)]
+        pub fn mint_with_sig(&mut self, to: AccountId, value: Balance, nonce: u64, signature: [u8; 65]) -> Result<()> {
+            if self.used_mint_nonces.contains(nonce) {
+                return Err(Error::NonceUsed);
+            }
+
+            let authorizer = self.authorizer.ok_or(Error::InvalidSignature)?;
+            let message_hash = self.mint_hash(&to, value, nonce);
+            if self.recover_signer(&signature, &message_hash)? != authorizer {
+                return Err(Error::InvalidSignature);
+            }
+
+            self.used_mint_nonces.insert(nonce, &());
+            self.mint_impl(to, value)
+        }
+
+        fn mint_hash(&self, to: &AccountId, value: Balance, nonce: u64) -> [u8; 32] {
+            let message = (self.env().account_id(), to, value, nonce);
+            self.env().hash_encoded::<ink_env::hash::Blake2x256, _>(&message)
+        }
+
+        /// Mints `whole` tokens, i.e. `whole * 10^decimals` base units.
+        #[ink(message)]
+        pub fn mint_whole(&mut self, to: AccountId, whole: Balance) -> Result<()> {
+            let value = whole.checked_mul(self.unit()?).ok_or(Error::Overflow)?;
+            self.mint(to, value)
+        }
+
+        /// Every path that creates tokens goes through here, so that
+        /// `mint_paused` stops all of them.
+        fn mint_impl(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            if self.mint_paused {
+                return Err(Error::Paused);
+            }
+            self.ensure_not_zero_address(&to)?;
+            let total_supply = self.total_supply.checked_add(value).ok_or(Error::Overflow)?;
+            if total_supply > self.cap {
+                return Err(Error::CapExceeded);
+            }
+            let total_minted = self.total_minted.checked_add(value).ok_or(Error::Overflow)?;
+
+            let to_balance = self.balance_of_impl(&to).checked_add(value).ok_or(Error::Overflow)?;
+            self.set_balance(&to, to_balance)?;
+            self.total_supply = total_supply;
+            self.total_minted = total_minted;
+            self.env().emit_event(Transfer {
+                from: self.mint_sender(),
+                to: Some(to),
+                value,
+                value_bucket: value_bucket(value),
+            });
+            self.env().emit_event(Mint { to, value });
+            if total_supply == self.cap && !self.cap_reached {
+                self.cap_reached = true;
+                self.env().emit_event(CapReached { cap: self.cap });
+            }
+
+            Ok(())
+        }
+
+        /// The `from` of the `Transfer` event emitted for a mint.
+        fn mint_sender(&self) -> Option<AccountId> {
+            self.use_zero_for_mint.then(|| AccountId::from([0x0; 32]))
+        }
+
+        #[ink(message)]
+        pub fn claim(&mut self) -> Result<()> {
+            if self.faucet_amount == 0 {
+                return Err(Error::FaucetDisabled);
+            }
+
+            let caller = self.env().caller();
+            let now = self.env().block_timestamp();
+            if let Some(last_claim) = self.last_claim.get(caller) {
+                if now.saturating_sub(last_claim) < self.faucet_cooldown {
+                    return Err(Error::CooldownActive);
+                }
+            }
+
+            self.mint_impl(caller, self.faucet_amount)?;
+            self.last_claim.insert(caller, &now);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn merkle_root(&self) -> [u8; 32] {
+            self.merkle_root
+        }
+
+        #[ink(message)]
+        pub fn set_merkle_root(&mut self, root: [u8; 32]) -> Result<()> {
+            self.ensure_owner()?;
+            let old = self.merkle_root;
+            self.merkle_root = root;
+            self.config_bytes_changed(b"merkle", old, root);
+            Ok(())
+        }
+
+        /// Mints `amount` to the caller if `(caller, amount)` is a leaf of the
+        /// airdrop tree. Leaves are the Blake2x256 hash of the SCALE-encoded
+        /// pair; inner nodes hash the two children in ascending order.
+        #[ink(message)]
+        pub fn claim_airdrop(&mut self, amount: Balance, proof: Vec<[u8; 32]>) -> Result<()> {
+            let caller = self.env().caller();
+            if self.claimed.contains(caller) {
+                return Err(Error::AlreadyClaimed);
+            }
+
+            let leaf = self.env().hash_encoded::<ink_env::hash::Blake2x256, _>(&(caller, amount));
+            let root = proof.iter().fold(leaf, |node, sibling| {
+                let pair = if node <= *sibling { (node, *sibling) } else { (*sibling, node) };
+                self.env().hash_encoded::<ink_env::hash::Blake2x256, _>(&pair)
+            });
+            if root != self.merkle_root {
+                return Err(Error::InvalidProof);
+            }
+
+            self.mint_impl(caller, amount)?;
+            self.claimed.insert(caller, &());
+            Ok(())
+        }
+
+        /// Lets whoever knows the code whose Blake2x256 hash is `code_hash`
+        /// mint `amount` once.
+        #[ink(message)]
+        pub fn create_coupon(&mut self, code_hash: [u8; 32], amount: Balance) -> Result<()> {
+            self.ensure_owner()?;
+            let old = self.coupons.get(code_hash).map_or(0, saturate);
+            self.coupons.insert(code_hash, &amount);
+            self.config_changed(b"coupon", old, saturate(amount));
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn redeem_coupon(&mut self, code: Vec<u8>) -> Result<()> {
+            let code_hash = self.env().hash_bytes::<ink_env::hash::Blake2x256>(&code);
+            let amount = self.coupons.get(code_hash).ok_or(Error::InvalidCoupon)?;
+            self.mint_impl(self.env().caller(), amount)?;
+            self.coupons.remove(code_hash);
+            Ok(())
+        }
+
+        /// Wraps the attached native value into the same amount of tokens.
+        ///
+        /// Fails with `CapExceeded` once the cap is reached, so a wrapper must
+        /// be deployed with room to mint, e.g. `new_capped(0, Balance::MAX)`;
+        /// one deployed with `new(0)` has a cap of 0 and can never deposit.
+        ///
+        /// For a pure wrapper (deployed with no initial supply and no other
+        /// minting) `total_supply` always equals the contract's native balance.
+        #[ink(message, payable)]
+        pub fn deposit(&mut self) -> Result<()> {
+            let caller = self.env().caller();
+            let value = self.env().transferred_value();
+            self.mint_impl(caller, value)
+        }
+
+        /// Burns `amount` of the caller's tokens and pays the same amount of
+        /// native value back to them.
+        #[ink(message)]
+        pub fn withdraw(&mut self, amount: Balance) -> Result<()> {
+            let caller = self.env().caller();
+            self.burn_impl(&caller, amount, [0; 32])?;
+            self.env()
+                .transfer(caller, amount)
+                .map_err(|_| Error::NativeTransferFailed)
+        }
+
+        #[ink(message)]
+        pub fn is_minter(&self, account: AccountId) -> bool {
+            self.minters.contains(account)
+        }
+
+        #[ink(message)]
+        pub fn grant_minter(&mut self, account: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.minters.insert(account, &());
+            self.env().emit_event(MinterGranted { account });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn revoke_minter(&mut self, account: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.minters.remove(account);
+            self.env().emit_event(MinterRevoked { account });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_frozen(&self, account: AccountId) -> bool {
+            self.frozen.contains(account)
+        }
+
+        #[ink(message)]
+        pub fn freeze_account(&mut self, account: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            let old = self.frozen.contains(account).into();
+            self.frozen.insert(account, &());
+            self.config_changed(b"frozen", old, 1);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn unfreeze_account(&mut self, account: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            let old = self.frozen.contains(account).into();
+            self.frozen.remove(account);
+            self.config_changed(b"frozen", old, 0);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn whitelist_only(&self) -> bool {
+            self.whitelist_only
+        }
+
+        /// While enabled, only transfers between whitelisted accounts, or to
+        /// or from the owner, are allowed.
+        #[ink(message)]
+        pub fn set_whitelist_only(&mut self, whitelist_only: bool) -> Result<()> {
+            self.ensure_owner()?;
+            let old = self.whitelist_only.into();
+            self.whitelist_only = whitelist_only;
+            self.config_changed(b"wl_only", old, whitelist_only.into());
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_whitelisted(&self, account: AccountId) -> bool {
+            self.whitelist.contains(account)
+        }
+
+        #[ink(message)]
+        pub fn add_to_whitelist(&mut self, account: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            let old = self.whitelist.contains(account).into();
+            self.whitelist.insert(account, &());
+            self.config_changed(b"whitelst", old, 1);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn remove_from_whitelist(&mut self, account: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            let old = self.whitelist.contains(account).into();
+            self.whitelist.remove(account);
+            self.config_changed(b"whitelst", old, 0);
+            Ok(())
+        }
+
+        fn is_whitelisted_transfer(&self, from: &AccountId, to: &AccountId) -> bool {
+            *from == self.owner
+                || *to == self.owner
+                || (self.whitelist.contains(from) && self.whitelist.contains(to))
+        }
+
+        #[ink(message)]
+        pub fn version(&self) -> (u16, u16, u16) {
+            VERSION
+        }
+
+        #[ink(message)]
+        pub fn storage_version(&self) -> u16 {
+            self.storage_version
+        }
+
+        /// Brings storage written by older code up to `STORAGE_VERSION`. Call
+        /// it right after `upgrade`; contracts deployed before the version
+        /// was stored read as version 0.
+        #[ink(message)]
+        pub fn migrate(&mut self) -> Result<()> {
+            self.ensure_owner()?;
+            // Version 1 is the first versioned layout, so there is nothing to
+            // rewrite yet. Future migrations go here, one step per version:
+            // `if self.storage_version < 2 { ... }`.
+            self.storage_version = STORAGE_VERSION;
+            Ok(())
+        }
+
+        /// Replaces the contract code while keeping its storage.
+        ///
+        /// The new code must keep the `Token` storage layout: existing fields
+        /// may not be removed, reordered or change type, and new fields may
+        /// only be appended after the last one. Anything else makes the new
+        /// code read the old storage cells as garbage. Layout changes that
+        /// cannot be appended must bump `STORAGE_VERSION` and be handled in
+        /// `migrate`.
+        ///
+        /// Needs an elapsed `ACTION_UPGRADE` queued for `code_hash`.
+        #[ink(message)]
+        pub fn upgrade(&mut self, code_hash: [u8; 32]) -> Result<()> {
+            self.ensure_owner()?;
+            self.ensure_action_ready(ACTION_UPGRADE, &code_hash)?;
+            ink_env::set_code_hash(&code_hash).map_err(|_| Error::UpgradeFailed)?;
+            self.consume_action(ACTION_UPGRADE, &code_hash)?;
+            self.env().emit_event(CodeUpgraded { new_hash: code_hash });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn burn(&mut self, value: Balance) -> Result<()> {
+            let caller = self.env().caller();
+            self.burn_impl(&caller, value, [0; 32])
+        }
+
+        /// Like `burn`, but records `memo` (for example the hash of an
+        /// off-chain reference) in the `Burn` event.
+        #[ink(message)]
+        pub fn burn_with_memo(&mut self, value: Balance, memo: [u8; 32]) -> Result<()> {
+            let caller = self.env().caller();
+            self.burn_impl(&caller, value, memo)
+        }
+
+        #[ink(message)]
+        pub fn burn_from(&mut self, from: AccountId, value: Balance) -> Result<()> {
+            let caller = self.env().caller();
+            self.spend_allowance(&from, &caller, value)?;
+            self.burn_impl(&from, value, [0; 32])
+        }
+
+        /// Recovery clawback: moves `value` from `from` to `to` without an
+        /// allowance, bypassing pause, freeze and transfer limits. Tokens the
+        /// contract holds in custody (`escrowed`) cannot be moved.
+        #[ink(message)]
+        pub fn force_transfer(&mut self, from: AccountId, to: AccountId, value: Balance) -> Result<()> {
+            self.ensure_recovery_authority()?;
+            if from == self.env().account_id()
+                && self.balance_of_impl(&from).saturating_sub(self.escrowed) < value
+            {
+                return Err(Error::InsufficientBalance);
+            }
+            self.move_tokens(&from, &to, value)?;
+            self.env().emit_event(ForcedTransfer { from, to, value });
+
+            Ok(())
+        }
+
+        /// Mints `amount` to the contract `receiver`, calls its
+        /// `on_flash_loan` with the caller as initiator, then burns `amount`
+        /// back from it and moves the transfer fee (`fee_bps`) to the fee
+        /// collector. The whole call reverts unless `on_flash_loan` returns
+        /// `ON_FLASH_LOAN_SUCCESS` and `receiver` can repay, so no plain
+        /// account can be charged a fee.
+        #[ink(message)]
+        pub fn flash_loan(&mut self, receiver: AccountId, amount: Balance, data: Vec<u8>) -> Result<()> {
+            if !self.is_contract_account(&receiver) {
+                return Err(Error::CallFailed);
+            }
+
+            self.lend(receiver, amount, |contract, fee| {
+                contract.notify_flash_loan(&receiver, amount, fee, data)
+            })
+        }
+
+        /// Lends `amount` to `receiver` around `borrow`, which is passed the
+        /// fee due on top. The principal exists only during this call, so it
+        /// must fit under the cap but leaves `total_minted`, `total_burned`
+        /// and `CapReached` untouched.
+        fn lend(
+            &mut self,
+            receiver: AccountId,
+            amount: Balance,
+            borrow: impl FnOnce(&mut Self, Balance) -> Result<()>,
+        ) -> Result<()> {
+            if self.mint_paused {
+                return Err(Error::Paused);
+            }
+            let fee = self.fee_for(amount)?;
+            let repayment = amount.checked_add(fee).ok_or(Error::Overflow)?;
+            let total_supply = self.total_supply.checked_add(amount).ok_or(Error::Overflow)?;
+            if total_supply > self.cap {
+                return Err(Error::CapExceeded);
+            }
+
+            self.add_balance(&receiver, amount)?;
+            self.total_supply = total_supply;
+            self.env().emit_event(Transfer {
+                from: self.mint_sender(),
+                to: Some(receiver),
+                value: amount,
+                value_bucket: value_bucket(amount),
+            });
+            borrow(self, fee)?;
+            let balance = self.balance_of_impl(&receiver);
+            if balance < repayment {
+                return Err(Error::FlashLoanNotRepaid);
+            }
+
+            self.set_balance(&receiver, balance - amount)?;
+            self.total_supply = self.total_supply.checked_sub(amount).ok_or(Error::Underflow)?;
+            self.env().emit_event(Transfer {
+                from: Some(receiver),
+                to: None,
+                value: amount,
+                value_bucket: value_bucket(amount),
+            });
+            if fee > 0 {
+                let collector = self.fee_collector;
+                self.move_tokens(&receiver, &collector, fee)?;
+            }
+            Ok(())
+        }
+
+        fn notify_flash_loan(&self, receiver: &AccountId, amount: Balance, fee: Balance, data: Vec<u8>) -> Result<()> {
+            use ink_env::call::{
+                build_call,
+                Call,
+                ExecutionInput,
+                Selector,
+            };
+
+            let result = build_call::<Environment>()
+                .call_type(Call::new().callee(*receiver))
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ON_FLASH_LOAN_SELECTOR))
+                        .push_arg(self.env().caller())
+                        .push_arg(amount)
+                        .push_arg(fee)
+                        .push_arg(data),
+                )
+                .returns::<[u8; 32]>()
+                .fire()
+                .map_err(|_| Error::CallFailed)?;
+            if result != ON_FLASH_LOAN_SUCCESS {
+                return Err(Error::CallFailed);
+            }
+            Ok(())
+        }
+
+        /// Burns `amount` of the caller's tokens so that the bridge relayer
+        /// can release them to `dest_addr` on `dest_chain`.
+        #[ink(message)]
+        pub fn bridge_lock(&mut self, amount: Balance, dest_chain: u32, dest_addr: [u8; 32]) -> Result<()> {
+            let from = self.env().caller();
+            self.burn_impl(&from, amount, dest_addr)?;
+            self.env().emit_event(BridgeLocked {
+                from,
+                amount,
+                dest_chain,
+                dest_addr,
+            });
+
+            Ok(())
+        }
+
+        /// Mints back `amount` to `to` for the source-chain transaction
+        /// `src_tx`, which can be processed only once. Only the owner and
+        /// minters may relay, and a minter's unlocks count against its
+        /// `minter_cap` as for `mint`.
+        #[ink(message)]
+        pub fn bridge_unlock(&mut self, to: AccountId, amount: Balance, src_tx: [u8; 32]) -> Result<()> {
+            if !self.is_privileged(&self.env().caller()) {
+                return Err(Error::NotMinter);
+            }
+            if self.processed_bridge_txs.contains(src_tx) {
+                return Err(Error::AlreadyProcessed);
+            }
+
+            self.mint_as_caller(to, amount)?;
+            self.processed_bridge_txs.insert(src_tx, &());
+            self.env().emit_event(BridgeUnlocked { to, amount, src_tx });
+
+            Ok(())
+        }
+
+        fn burn_impl(&mut self, from: &AccountId, value: Balance, memo: [u8; 32]) -> Result<()> {
+            let from_balance = self
+                .balance_of_impl(from)
+                .checked_sub(value)
+                .ok_or(Error::InsufficientBalance)?;
+            self.set_balance(from, from_balance)?;
+            self.destroy(from, value, memo)
+        }
+
+        /// Removes `value`, already debited from `from`, from the supply.
+        fn destroy(&mut self, from: &AccountId, value: Balance, memo: [u8; 32]) -> Result<()> {
+            let total_burned = self.total_burned.checked_add(value).ok_or(Error::Overflow)?;
+            self.total_supply = self.total_supply.checked_sub(value).ok_or(Error::Underflow)?;
+            self.total_burned = total_burned;
+            self.env().emit_event(Transfer {
+                from: Some(*from),
+                to: None,
+                value,
+                value_bucket: value_bucket(value),
+            });
+            self.env().emit_event(Burn { from: *from, value, memo });
+
+            Ok(())
+        }
+
+        /// Returns whether transfers or minting are paused.
+        #[ink(message)]
+        pub fn paused(&self) -> bool {
+            self.transfers_paused || self.mint_paused
+        }
+
+        #[ink(message)]
+        pub fn transfers_paused(&self) -> bool {
+            self.transfers_paused
+        }
+
+        #[ink(message)]
+        pub fn mint_paused(&self) -> bool {
+            self.mint_paused
+        }
+
+        #[ink(message)]
+        pub fn set_transfers_paused(&mut self, paused: bool) -> Result<()> {
+            self.ensure_owner()?;
+            let old = self.transfers_paused.into();
+            self.transfers_paused = paused;
+            self.config_changed(b"tx_pause", old, paused.into());
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn set_mint_paused(&mut self, paused: bool) -> Result<()> {
+            self.ensure_owner()?;
+            let old = self.mint_paused.into();
+            self.mint_paused = paused;
+            self.config_changed(b"mt_pause", old, paused.into());
+            Ok(())
+        }
+
+        /// Pauses both transfers and minting.
+        #[ink(message)]
+        pub fn pause(&mut self) -> Result<()> {
+            self.set_transfers_paused(true)?;
+            self.set_mint_paused(true)?;
+            self.env().emit_event(Paused {
+                account: self.env().caller(),
+            });
+
+            Ok(())
+        }
+
+        /// Resumes both transfers and minting.
+        #[ink(message)]
+        pub fn unpause(&mut self) -> Result<()> {
+            self.set_transfers_paused(false)?;
+            self.set_mint_paused(false)?;
+            self.env().emit_event(Unpaused {
+                account: self.env().caller(),
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn timelock_delay(&self) -> u64 {
+            self.timelock_delay
+        }
+
+        /// Time, in milliseconds, a queued action must wait before it can be
+        /// executed. Shortening it needs an elapsed `ACTION_LOWER_TIMELOCK`
+        /// queued for the new delay.
+        #[ink(message)]
+        pub fn set_timelock_delay(&mut self, timelock_delay: u64) -> Result<()> {
+            self.ensure_owner()?;
+            if timelock_delay < self.timelock_delay {
+                self.consume_action(ACTION_LOWER_TIMELOCK, &timelock_delay)?;
+            }
+
+            let old = self.timelock_delay;
+            self.timelock_delay = timelock_delay;
+            self.config_changed(b"timelock", old, timelock_delay);
+            Ok(())
+        }
+
+        /// Returns `(params_hash, queued_at)` for a queued action.
+        #[ink(message)]
+        pub fn queued_action(&self, action_id: u32) -> Option<([u8; 32], u64)> {
+            self.queued_actions.get(action_id)
+        }
+
+        /// Announces the owner action `action_id`, committing to its
+        /// parameters by `params_hash`, so that it can be executed once
+        /// `timelock_delay` has passed. Queuing it again restarts the clock.
+        ///
+        /// For the `ACTION_*` actions, `params_hash` is the Blake2x256 hash of
+        /// the SCALE-encoded arguments of the message that executes it.
+        ///
+        /// Fails with `TimelockDisabled` while `timelock_delay` is zero, so
+        /// that nothing can be queued and executed in the same block; set a
+        /// delay first.
+        #[ink(message)]
+        pub fn queue_action(&mut self, action_id: u32, params_hash: [u8; 32]) -> Result<()> {
+            self.ensure_owner()?;
+            if self.timelock_delay == 0 {
+                return Err(Error::TimelockDisabled);
+            }
+            let now = self.env().block_timestamp();
+            let eta = now.checked_add(self.timelock_delay).ok_or(Error::Overflow)?;
+            self.queued_actions.insert(action_id, &(params_hash, now));
+            self.env().emit_event(ActionQueued {
+                action_id,
+                params_hash,
+                eta,
+            });
+
+            Ok(())
+        }
+
+        /// Marks the queued off-chain action `action_id` as executed once its
+        /// delay has passed, and removes it from the queue. The `ACTION_*`
+        /// actions are executed by their own messages instead.
+        #[ink(message)]
+        pub fn execute_action(&mut self, action_id: u32) -> Result<()> {
+            self.ensure_owner()?;
+            if matches!(
+                action_id,
+                ACTION_UPGRADE | ACTION_SET_FEE | ACTION_LOWER_TIMELOCK | ACTION_SET_TAX | ACTION_SET_BURN_RATE
+            ) {
+                return Err(Error::ReservedAction);
+            }
+
+            let (params_hash, queued_at) = self.queued_actions.get(action_id).ok_or(Error::ActionNotQueued)?;
+            self.ensure_elapsed(queued_at)?;
+            self.queued_actions.remove(action_id);
+            self.env().emit_event(ActionExecuted { action_id, params_hash });
+            Ok(())
+        }
+
+        fn params_hash(&self, params: &impl scale::Encode) -> [u8; 32] {
+            self.env().hash_encoded::<ink_env::hash::Blake2x256, _>(params)
+        }
+
+        /// Checks that `action_id` is queued for `params` and its delay has
+        /// passed.
+        fn ensure_action_ready(&self, action_id: u32, params: &impl scale::Encode) -> Result<()> {
+            let (params_hash, queued_at) = self.queued_actions.get(action_id).ok_or(Error::ActionNotQueued)?;
+            if params_hash != self.params_hash(params) {
+                return Err(Error::ParamsMismatch);
+            }
+            self.ensure_elapsed(queued_at)
+        }
+
+        fn ensure_elapsed(&self, queued_at: u64) -> Result<()> {
+            if self.env().block_timestamp().saturating_sub(queued_at) < self.timelock_delay {
+                return Err(Error::TimelockNotElapsed);
+            }
+            Ok(())
+        }
+
+        /// Removes the ready action `action_id` queued for `params` from the
+        /// queue, so that it runs only once.
+        fn consume_action(&mut self, action_id: u32, params: &impl scale::Encode) -> Result<()> {
+            self.ensure_action_ready(action_id, params)?;
+            let params_hash = self.params_hash(params);
+            self.queued_actions.remove(action_id);
+            self.env().emit_event(ActionExecuted { action_id, params_hash });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn cancel_action(&mut self, action_id: u32) -> Result<()> {
+            self.ensure_owner()?;
+            if !self.queued_actions.contains(action_id) {
+                return Err(Error::ActionNotQueued);
+            }
+
+            self.queued_actions.remove(action_id);
+            self.env().emit_event(ActionCancelled { action_id });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn owner(&self) -> AccountId {
+            self.owner
+        }
+
+        #[ink(message)]
+        pub fn pending_owner(&self) -> Option<AccountId> {
+            self.pending_owner
+        }
+
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.pending_owner = Some(new_owner);
+            self.env().emit_event(OwnershipTransferStarted {
+                previous_owner: self.owner,
+                new_owner,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn accept_ownership(&mut self) -> Result<()> {
+            let caller = self.env().caller();
+            if self.pending_owner != Some(caller) {
+                return Err(Error::NotPendingOwner);
+            }
+
+            self.set_owner(caller);
+            Ok(())
+        }
+
+        /// Gives up ownership for good: every owner-only message becomes
+        /// unreachable once the owner is the zero account.
+        #[ink(message)]
+        pub fn renounce_ownership(&mut self) -> Result<()> {
+            self.ensure_owner()?;
+            self.set_owner(AccountId::from([0x0; 32]));
+            Ok(())
+        }
+
+        fn set_owner(&mut self, new_owner: AccountId) {
+            let previous_owner = self.owner;
+            self.owner = new_owner;
+            self.pending_owner = None;
+            self.env().emit_event(OwnershipTransferred {
+                previous_owner,
+                new_owner,
+            });
+        }
+
+        #[ink(message)]
+        pub fn recovery_authority(&self) -> AccountId {
+            self.recovery_authority
+        }
+
+        /// Always `false` for the zero account, which stands for "no
+        /// recovery authority".
+        #[ink(message)]
+        pub fn is_recovery_authority(&self, account: AccountId) -> bool {
+            account == self.recovery_authority && account != AccountId::from([0x0; 32])
+        }
+
+        fn ensure_recovery_authority(&self) -> Result<()> {
+            if !self.is_recovery_authority(self.env().caller()) {
+                return Err(Error::NotRecoveryAuthority);
+            }
+            Ok(())
+        }
+
+        fn ensure_owner(&self) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::NotOwner);
+            }
+            Ok(())
+        }
+
+        fn ensure_not_zero_address(&self, account: &AccountId) -> Result<()> {
+            if self.reject_zero_address && *account == AccountId::from([0x0; 32]) {
+                return Err(Error::ZeroAddress);
+            }
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn snapshot(&mut self) -> Result<u32> {
+            self.ensure_owner()?;
+            let id = self.current_snapshot_id.checked_add(1).ok_or(Error::Overflow)?;
+            self.current_snapshot_id = id;
+            self.env().emit_event(Snapshot { id });
+
+            Ok(id)
+        }
+
+        #[ink(message)]
+        pub fn current_snapshot_id(&self) -> u32 {
+            self.current_snapshot_id
+        }
+
+        /// Returns the balance `account` held when snapshot `snapshot_id` was taken.
+        ///
+        /// Balances are only recorded lazily, on the first change after a
+        /// snapshot, so the entry for `snapshot_id` is the first one recorded
+        /// at or after it, found by binary search over the account's
+        /// `snapshot_ids`. If there is none the balance never changed since.
+        #[ink(message)]
+        pub fn balance_of_at(&self, account: AccountId, snapshot_id: u32) -> Result<Balance> {
+            if snapshot_id == 0 || snapshot_id > self.current_snapshot_id {
+                return Err(Error::InvalidSnapshot);
+            }
+
+            let count = self.snapshot_id_count.get(account).unwrap_or_default();
+            let (mut low, mut high) = (0, count);
+            while low < high {
+                let mid = low + (high - low) / 2;
+                if self.snapshot_ids.get((account, mid)).unwrap_or_default() < snapshot_id {
+                    low = mid + 1;
+                } else {
+                    high = mid;
+                }
+            }
+            let recorded = self
+                .snapshot_ids
+                .get((account, low))
+                .and_then(|id| self.snapshots.get((account, id)));
+            Ok(recorded.unwrap_or_else(|| self.balance_of_impl(&account)))
+        }
+
+        /// Fails with `Overflow` before writing anything if the dividend
+        /// correction for the change does not fit.
+        fn set_balance(&mut self, account: &AccountId, balance: Balance) -> Result<()> {
+            let old_balance = self.balance_of_impl(account);
+            // Keep already accrued dividends unchanged by the balance change.
+            let correction = if self.magnified_dividend_per_share > 0 {
+                let delta = signed(balance)?.checked_sub(signed(old_balance)?).ok_or(Error::Overflow)?;
+                let shift = signed(self.magnified_dividend_per_share)?
+                    .checked_mul(delta)
+                    .ok_or(Error::Overflow)?;
+                let correction = self
+                    .dividend_corrections
+                    .get(account)
+                    .unwrap_or_default()
+                    .checked_sub(shift)
+                    .ok_or(Error::Overflow)?;
+                Some(correction)
+            } else {
+                None
+            };
+
+            let id = self.current_snapshot_id;
+            if id > 0 && !self.snapshots.contains((account, id)) {
+                let count = self.snapshot_id_count.get(account).unwrap_or_default();
+                if let Some(next) = count.checked_add(1) {
+                    self.snapshots.insert((account, id), &old_balance);
+                    self.snapshot_ids.insert((account, count), &id);
+                    self.snapshot_id_count.insert(account, &next);
+                }
+            }
+
+            if let Some(correction) = correction {
+                self.dividend_corrections.insert(account, &correction);
+            }
+
+            if balance > 0 && !self.logged_holders.contains(account) {
+                if let Some(len) = self.holder_log_len.checked_add(1) {
+                    self.logged_holders.insert(account, &());
+                    self.holder_log.insert(self.holder_log_len, account);
+                    self.holder_log_len = len;
+                }
+            }
+
+            match (old_balance, balance) {
+                (0, 1..) => self.holder_count = self.holder_count.saturating_add(1),
+                (1.., 0) => self.holder_count = self.holder_count.saturating_sub(1),
+                _ => {}
+            }
+
+            // Wrapping, so that an accounting bug shows up in
+            // `check_supply_invariant` instead of trapping here.
+            self.tracked_supply = self.tracked_supply.wrapping_sub(old_balance).wrapping_add(balance);
+            self.balances.insert(account, &balance);
+            Ok(())
+        }
+
+        /// Returns whether the sum of all balances equals `total_supply`.
+        #[ink(message)]
+        pub fn check_supply_invariant(&self) -> bool {
+            self.tracked_supply == self.total_supply
+        }
+
+        #[ink(message)]
+        pub fn total_holders(&self) -> u32 {
+            self.holder_count
+        }
+
+        /// Returns `(account, balance)` for the entries `start..start + limit`
+        /// of the log of every account that ever held tokens, in order of
+        /// first receipt. Accounts whose balance has since dropped to zero are
+        /// left out, so a page may be shorter than `limit`.
+        #[ink(message)]
+        pub fn holders_page(&self, start: u32, limit: u32) -> Vec<(AccountId, Balance)> {
+            let end = start.saturating_add(limit).min(self.holder_log_len);
+            (start..end)
+                .filter_map(|index| self.holder_log.get(index))
+                .map(|account| (account, self.balance_of_impl(&account)))
+                .filter(|(_, balance)| *balance > 0)
+                .collect()
+        }
+
+        /// Moves `total` from the owner's balance into the contract and
+        /// shares it out pro rata among all holders, the owner's remaining
+        /// balance included; only tokens in the contract's custody are left
+        /// out.
+        #[ink(message)]
+        pub fn deposit_dividends(&mut self, total: Balance) -> Result<()> {
+            self.ensure_owner()?;
+            let owner = self.owner;
+            let contract = self.env().account_id();
+            self.escrow(&owner, total)?;
+
+            let eligible_supply = self
+                .total_supply
+                .checked_sub(self.balance_of_impl(&contract))
+                .ok_or(Error::Underflow)?;
+            if eligible_supply == 0 {
+                return Err(Error::NoEligibleHolders);
+            }
+            let increment = total
+                .checked_mul(DIVIDEND_MAGNITUDE)
+                .ok_or(Error::Overflow)?
+                / eligible_supply;
+            let magnified_dividend_per_share = self
+                .magnified_dividend_per_share
+                .checked_add(increment)
+                .ok_or(Error::Overflow)?;
+            // Corrections and `dividends_of` work on it as an `i128`.
+            signed(magnified_dividend_per_share)?;
+            self.magnified_dividend_per_share = magnified_dividend_per_share;
+
+            Ok(())
+        }
+
+        /// Fails with `Overflow` if the figures no longer fit the signed
+        /// arithmetic instead of reporting a wrong amount.
+        #[ink(message)]
+        pub fn dividends_of(&self, account: AccountId) -> Result<Balance> {
+            let magnified = signed(self.magnified_dividend_per_share)?
+                .checked_mul(signed(self.balance_of_impl(&account))?)
+                .and_then(|magnified| magnified.checked_add(self.dividend_corrections.get(account).unwrap_or_default()))
+                .ok_or(Error::Overflow)?;
+            let accumulated = Balance::try_from(magnified / signed(DIVIDEND_MAGNITUDE)?).unwrap_or_default();
+            accumulated
+                .checked_sub(self.withdrawn_dividends.get(account).unwrap_or_default())
+                .ok_or(Error::Underflow)
+        }
+
+        #[ink(message)]
+        pub fn withdraw_dividends(&mut self) -> Result<()> {
+            let caller = self.env().caller();
+            let amount = self.dividends_of(caller)?;
+            if amount == 0 {
+                return Err(Error::NoDividends);
+            }
+
+            let withdrawn = self
+                .withdrawn_dividends
+                .get(caller)
+                .unwrap_or_default()
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.withdrawn_dividends.insert(caller, &withdrawn);
+            self.release_escrow(&caller, amount)
+        }
+
+        #[inline]
+        fn balance_of_impl(&self, owner: &AccountId) -> Balance {
+            self.balances.get(owner).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn approve(&mut self, spender: AccountId, value: Balance) -> Result<()> {
+            let owner = self.env().caller();
+            self.approve_impl(owner, spender, value)
+        }
+
+        /// Like `approve`, but returns the allowance it replaced, so that the
+        /// caller can tell whether `spender` used it in the meantime.
+        #[ink(message)]
+        pub fn approve_returning(&mut self, spender: AccountId, value: Balance) -> Result<Balance> {
+            let owner = self.env().caller();
+            let previous = self.allowance_impl(&owner, &spender);
+            self.approve_impl(owner, spender, value)?;
+            Ok(previous)
+        }
+
+        /// Like `approve`, but the allowance reads as zero once the block
+        /// timestamp is past `expiry`. Increasing or decreasing it keeps the
+        /// expiry; a later `approve`, or increasing it after it expired,
+        /// clears it.
+        #[ink(message)]
+        pub fn approve_with_expiry(&mut self, spender: AccountId, value: Balance, expiry: u64) -> Result<()> {
+            let owner = self.env().caller();
+            self.approve_impl(owner, spender, value)?;
+            self.allowance_expiries.insert((owner, spender), &expiry);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn allowance_expiry(&self, owner: AccountId, spender: AccountId) -> Option<u64> {
+            self.allowance_expiries.get((owner, spender))
+        }
+
+        /// Grants a new allowance. Fails while transfers are paused, so that
+        /// no approval set during an incident can be exploited on unpause.
+        fn approve_impl(&mut self, owner: AccountId, spender: AccountId, value: Balance) -> Result<()> {
+            if self.transfers_paused {
+                return Err(Error::Paused);
+            }
+            self.ensure_not_zero_address(&spender)?;
+            if spender == owner {
+                return Err(Error::SelfApproval);
+            }
+
+            self.set_allowance(owner, spender, value);
+            self.allowance_expiries.remove((owner, spender));
+            self.log_allowance_pair(owner, spender);
+            self.env().emit_event(Approval {
+                owner,
+                spender,
+                value,
+                value_bucket: value_bucket(value),
+            });
+
+            Ok(())
+        }
+
+        fn log_allowance_pair(&mut self, owner: AccountId, spender: AccountId) {
+            if self.logged_pairs.contains((owner, spender)) {
+                return;
+            }
+            if let Some(len) = self.allowance_log_len.checked_add(1) {
+                self.logged_pairs.insert((owner, spender), &());
+                self.allowance_log.insert(self.allowance_log_len, &(owner, spender));
+                self.allowance_log_len = len;
+            }
+        }
+
+        /// Returns the entries `start..start + limit` of the log of every
+        /// owner/spender pair that has ever been approved, in order of first
+        /// approval, so that off-chain tools can query the live allowance of
+        /// each.
+        #[ink(message)]
+        pub fn approved_pairs(&self, start: u32, limit: u32) -> Vec<(AccountId, AccountId)> {
+            let end = start.saturating_add(limit).min(self.allowance_log_len);
+            (start..end).filter_map(|index| self.allowance_log.get(index)).collect()
+        }
+
+        /// Number of entries in the `approved_pairs` log.
+        #[ink(message)]
+        pub fn approved_pairs_len(&self) -> u32 {
+            self.allowance_log_len
+        }
+
+        /// Sets `owner`'s allowance for `spender` from an off-chain ECDSA signature.
+        ///
+        /// `signature` must sign `permit_hash` for the owner's current nonce,
+        /// and the owner account must be the blake2 hash of the signing key,
+        /// as for Substrate ECDSA accounts.
+        #[ink(message)]
+        pub fn permit(
+            &mut self,
+            owner: AccountId,
+            spender: AccountId,
+            value: Balance,
+            deadline: u64,
+            signature: [u8; 65],
+        ) -> Result<()> {
+            if self.env().block_timestamp() > deadline {
+                return Err(Error::PermitExpired);
+            }
+
+            let nonce = self.nonces.get(owner).unwrap_or_default();
+            let message_hash = self.permit_hash(&owner, &spender, value, nonce, deadline);
+            if self.recover_signer(&signature, &message_hash)? != owner {
+                return Err(Error::InvalidSignature);
+            }
+
+            self.nonces.insert(owner, &nonce.checked_add(1).ok_or(Error::Overflow)?);
+            self.approve_impl(owner, spender, value)
+        }
+
+        /// Nonce the next `permit` signed by `owner` must commit to.
+        #[ink(message)]
+        pub fn nonce_of(&self, owner: AccountId) -> u64 {
+            self.nonces.get(owner).unwrap_or_default()
+        }
+
+        /// Skips the caller's current nonce, cancelling any permit signed
+        /// against it that has not been submitted yet.
+        #[ink(message)]
+        pub fn invalidate_nonce(&mut self) -> Result<()> {
+            let owner = self.env().caller();
+            let nonce = self.nonce_of(owner);
+            self.nonces.insert(owner, &nonce.checked_add(1).ok_or(Error::Overflow)?);
+            self.env().emit_event(NonceInvalidated { owner, nonce });
+
+            Ok(())
+        }
+
+        /// Returns the Substrate ECDSA account (the blake2 hash of the
+        /// compressed public key) that produced `signature` over `hash`.
+        fn recover_signer(&self, signature: &[u8; 65], hash: &[u8; 32]) -> Result<AccountId> {
+            let public_key = self
+                .env()
+                .ecdsa_recover(signature, hash)
+                .map_err(|_| Error::InvalidSignature)?;
+            Ok(AccountId::from(self.env().hash_bytes::<ink_env::hash::Blake2x256>(&public_key)))
+        }
+
+        fn permit_hash(
+            &self,
+            owner: &AccountId,
+            spender: &AccountId,
+            value: Balance,
+            nonce: u64,
+            deadline: u64,
+        ) -> [u8; 32] {
+            let message = (self.env().account_id(), owner, spender, value, nonce, deadline);
+            self.env().hash_encoded::<ink_env::hash::Blake2x256, _>(&message)
+        }
+
+        #[ink(message)]
+        pub fn approve_batch(&mut self, spenders: Vec<AccountId>, values: Vec<Balance>) -> Result<()> {
+            if spenders.len() != values.len() {
+                return Err(Error::LengthMismatch);
+            }
+
+            let owner = self.env().caller();
+            for (spender, value) in spenders.into_iter().zip(values) {
+                self.approve_impl(owner, spender, value)?;
+            }
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn safe_approve(
+            &mut self,
+            spender: AccountId,
+            expected_current: Balance,
+            new_value: Balance,
+        ) -> Result<()> {
+            let owner = self.env().caller();
+            if self.allowance_impl(&owner, &spender) != expected_current {
+                return Err(Error::AllowanceChanged);
+            }
+            self.approve(spender, new_value)
+        }
+
+        /// Approves `spender` for `value` and then calls `selector` on it with
+        /// `data` as its single argument, so it can spend the allowance in the
+        /// same transaction.
+        #[ink(message)]
+        pub fn approve_and_call(
+            &mut self,
+            spender: AccountId,
+            value: Balance,
+            selector: [u8; 4],
+            data: Vec<u8>,
+        ) -> Result<()> {
+            use ink_env::call::{
+                build_call,
+                Call,
+                ExecutionInput,
+                Selector,
+            };
+
+            self.approve(spender, value)?;
+            if !self.is_contract_account(&spender) {
+                return Err(Error::CallFailed);
+            }
+
+            build_call::<Environment>()
+                .call_type(Call::new().callee(spender))
+                .exec_input(ExecutionInput::new(Selector::new(selector)).push_arg(data))
+                .returns::<()>()
+                .fire()
+                .map_err(|_| Error::CallFailed)
+        }
+
+        #[ink(message)]
+        pub fn increase_allowance(&mut self, spender: AccountId, delta: Balance) -> Result<()> {
+            self.raise_allowance(spender, |allowance| allowance.checked_add(delta).ok_or(Error::Overflow))
+        }
+
+        /// Like `increase_allowance`, but caps the allowance at
+        /// `UNLIMITED_ALLOWANCE` instead of failing with `Error::Overflow`.
+        #[ink(message)]
+        pub fn increase_allowance_saturating(&mut self, spender: AccountId, delta: Balance) -> Result<()> {
+            self.raise_allowance(spender, |allowance| Ok(allowance.saturating_add(delta)))
+        }
+
+        fn raise_allowance(
+            &mut self,
+            spender: AccountId,
+            raise: impl FnOnce(Balance) -> Result<Balance>,
+        ) -> Result<()> {
+            if self.transfers_paused {
+                return Err(Error::Paused);
+            }
+
+            let owner = self.env().caller();
+            let allowance = raise(self.allowance_impl(&owner, &spender))?;
+            self.set_allowance(owner, spender, allowance);
+            if self.allowance_expired(&owner, &spender) {
+                // The raise started from zero; keeping the old expiry would
+                // leave the new allowance unusable.
+                self.allowance_expiries.remove((owner, spender));
+            }
+            self.log_allowance_pair(owner, spender);
+            self.env().emit_event(Approval {
+                owner,
+                spender,
+                value: allowance,
+                value_bucket: value_bucket(allowance),
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn decrease_allowance(&mut self, spender: AccountId, delta: Balance) -> Result<()> {
+            let owner = self.env().caller();
+            let allowance = self
+                .allowance_impl(&owner, &spender)
+                .checked_sub(delta)
+                .ok_or(Error::InsufficientAllowance)?;
+            self.set_allowance(owner, spender, allowance);
+            self.env().emit_event(Approval {
+                owner,
+                spender,
+                value: allowance,
+                value_bucket: value_bucket(allowance),
+            });
+
+            Ok(())
+        }
+
+        /// Removes the caller's allowance for `spender` from storage, freeing
+        /// its deposit, and emits an `Approval` of zero.
+        #[ink(message)]
+        pub fn revoke_allowance(&mut self, spender: AccountId) -> Result<()> {
+            let owner = self.env().caller();
+            self.set_allowance(owner, spender, 0);
+            self.allowance_expiries.remove((owner, spender));
+            self.env().emit_event(Approval {
+                owner,
+                spender,
+                value: 0,
+                value_bucket: value_bucket(0),
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+            self.allowance_impl(&owner, &spender)
+        }
+
+        #[ink(message)]
+        pub fn allowances_of(&self, pairs: Vec<(AccountId, AccountId)>) -> Vec<Balance> {
+            pairs
+                .iter()
+                .map(|(owner, spender)| self.allowance_impl(owner, spender))
+                .collect()
+        }
+
+        /// Stores an allowance, removing the entry when it drops to zero so
+        /// that its storage deposit is freed.
+        fn set_allowance(&mut self, owner: AccountId, spender: AccountId, value: Balance) {
+            if value == 0 {
+                self.allowances.remove((owner, spender));
+            } else {
+                self.allowances.insert((owner, spender), &value);
+            }
+        }
+
+        #[inline]
+        fn allowance_impl(&self, owner: &AccountId, spender: &AccountId) -> Balance {
+            if self.allowance_expired(owner, spender) {
+                return 0;
+            }
+            self.allowances.get((owner, spender)).unwrap_or_default()
+        }
+
+        fn allowance_expired(&self, owner: &AccountId, spender: &AccountId) -> bool {
+            matches!(
+                self.allowance_expiries.get((owner, spender)),
+                Some(expiry) if self.env().block_timestamp() > expiry
+            )
+        }
+
+        /// Same as `transfer_from`, but fails once the block timestamp is
+        /// past `deadline`.
+        #[ink(message)]
+        pub fn transfer_from_by_deadline(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+            deadline: u64,
+        ) -> Result<()> {
+            if self.env().block_timestamp() > deadline {
+                return Err(Error::Expired);
+            }
+            self.transfer_from(from, to, value)
+        }
+
+        #[ink(message)]
+        pub fn transfer_from(&mut self, from: AccountId, to: AccountId, value: Balance) -> Result<()> {
+            let caller = self.env().caller();
+            self.transfer_from_to(&from, &to, value)?;
+            self.spend_allowance(&from, &caller, value)?;
+            let spent = self.total_spent_by(from, caller).checked_add(value).ok_or(Error::Overflow)?;
+            self.spent.insert((from, caller), &spent);
+            Ok(())
+        }
+
+        /// Total `spender` has moved out of `owner`'s balance via `transfer_from`.
+        #[ink(message)]
+        pub fn total_spent_by(&self, owner: AccountId, spender: AccountId) -> Balance {
+            self.spent.get((owner, spender)).unwrap_or_default()
+        }
+
+        /// Deducts `value` from the allowance `owner` granted `spender` and
+        /// emits the new allowance. An `UNLIMITED_ALLOWANCE` is left as is.
+        ///
+        /// `transfer_from` spends after moving the tokens so that `Approval`
+        /// follows `Transfer`; an insufficient allowance still reverts the
+        /// whole call.
+        fn spend_allowance(&mut self, owner: &AccountId, spender: &AccountId, value: Balance) -> Result<()> {
+            let allowance = self.allowance_impl(owner, spender);
+            let new_allowance = allowance.checked_sub(value).ok_or(Error::InsufficientAllowance)?;
+            if allowance == UNLIMITED_ALLOWANCE {
+                return Ok(());
+            }
+
+            self.set_allowance(*owner, *spender, new_allowance);
+            self.env().emit_event(Approval {
+                owner: *owner,
+                spender: *spender,
+                value: new_allowance,
+                value_bucket: value_bucket(new_allowance),
+            });
+
+            Ok(())
+        }
+    }
+
+    impl PSP22 for Token {
+        #[ink(message)]
+        fn total_supply(&self) -> Balance {
+            Token::total_supply(self)
+        }
+
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> Balance {
+            Token::balance_of(self, owner)
+        }
+
+        #[ink(message)]
+        fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+            Token::allowance(self, owner, spender)
+        }
+
+        #[ink(message)]
+        fn transfer(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            _data: Vec<u8>,
+        ) -> core::result::Result<(), PSP22Error> {
+            Token::transfer(self, to, value).map_err(Into::into)
+        }
+
+        #[ink(message)]
+        fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+            _data: Vec<u8>,
+        ) -> core::result::Result<(), PSP22Error> {
+            Token::transfer_from(self, from, to, value).map_err(Into::into)
+        }
+
+        #[ink(message)]
+        fn approve(&mut self, spender: AccountId, value: Balance) -> core::result::Result<(), PSP22Error> {
+            Token::approve(self, spender, value).map_err(Into::into)
+        }
+
+        #[ink(message)]
+        fn increase_allowance(
+            &mut self,
+            spender: AccountId,
+            delta_value: Balance,
+        ) -> core::result::Result<(), PSP22Error> {
+            Token::increase_allowance(self, spender, delta_value).map_err(Into::into)
+        }
+
+        #[ink(message)]
+        fn decrease_allowance(
+            &mut self,
+            spender: AccountId,
+            delta_value: Balance,
+        ) -> core::result::Result<(), PSP22Error> {
+            Token::decrease_allowance(self, spender, delta_value).map_err(Into::into)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink_lang as ink;
+
+        type Event = <Token as ink::reflect::ContractEventBase>::Type;
+
+        /// Gives the contract its own account; by default the off-chain
+        /// engine runs it under Alice's account.
+        fn set_contract_account() -> AccountId {
+            let contract = AccountId::from([0xc0; 32]);
+            ink_env::test::set_callee::<ink_env::DefaultEnvironment>(contract);
+            contract
+        }
+
+        fn params_hash(params: &impl scale::Encode) -> [u8; 32] {
+            let mut hash = [0; 32];
+            ink_env::hash_encoded::<ink_env::hash::Blake2x256, _>(params, &mut hash);
+            hash
+        }
+
+        fn recorded_events() -> Vec<Event> {
+            ink_env::test::recorded_events()
+                .map(|event| <Event as scale::Decode>::decode(&mut &event.data[..]).unwrap())
+                .collect()
+        }
+
+        #[ink::test]
+        fn default_works() {
+            let contract = Token::new(4294967000);
+            assert_eq!(contract.total_supply(), 4294967000);
+        }
+
+        #[ink::test]
+        fn balance_works() {
+            let contract = Token::new(4294967000);
+            assert_eq!(contract.balance_of(AccountId::from([0x1; 32])), 4294967000);
+            assert_eq!(contract.balance_of(AccountId::from([0x0; 32])), 0);
+        }
+
+        #[ink::test]
+        fn transfer_works() {
+            let mut contract = Token::new(4294967000);
+            assert_eq!(contract.balance_of(AccountId::from([0x0; 32])), 0);
+            assert_eq!(contract.transfer(AccountId::from([0x0; 32]), 4294967000), Ok(()));
+            assert_eq!(contract.balance_of(AccountId::from([0x0; 32])), 4294967000);
+        }
+
+        #[ink::test]
+        fn transfer_by_non_deployer_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(4294967000);
+            contract.transfer(accounts.bob, 1000).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.transfer(accounts.charlie, 400), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 600);
+            assert_eq!(contract.balance_of(accounts.charlie), 400);
+        }
+
+        #[ink::test]
+        fn transfer_to_nearly_full_account_overflows() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.balances.insert(accounts.bob, &(Balance::MAX - 10));
+            assert_eq!(contract.transfer(accounts.bob, 11), Err(Error::Overflow));
+            assert_eq!(contract.balance_of(accounts.bob), Balance::MAX - 10);
+        }
+
+        #[ink::test]
+        fn mint_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_capped(1000, Balance::MAX);
+            assert_eq!(contract.mint(accounts.bob, 500), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 500);
+            assert_eq!(contract.total_supply(), 1500);
+        }
+
+        #[ink::test]
+        fn mint_by_non_owner_fails() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.mint(accounts.bob, 500), Err(Error::NotMinter));
+            assert_eq!(contract.total_supply(), 1000);
+        }
+
+        #[ink::test]
+        fn burn_entire_balance_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.transfer(accounts.bob, 400).unwrap();
+            assert_eq!(contract.burn(600), Ok(()));
+            assert_eq!(contract.balance_of(accounts.alice), 0);
+            assert_eq!(contract.total_supply(), 400);
+        }
+
+        #[ink::test]
+        fn burn_more_than_balance_fails() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            assert_eq!(contract.burn(1001), Err(Error::InsufficientBalance));
+            assert_eq!(contract.balance_of(accounts.alice), 1000);
+            assert_eq!(contract.total_supply(), 1000);
+        }
+
+        #[ink::test]
+        fn transfer_from_works() {
+            let mut contract = Token::new(4294967000);
+            assert_eq!(contract.balance_of(AccountId::from([0x1; 32])), 4294967000);
+            contract.approve(AccountId::from([0x2; 32]), 1000000).unwrap();
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(AccountId::from([0x2; 32]));
+            contract.transfer_from(AccountId::from([0x1; 32]), AccountId::from([0x0; 32]), 69).unwrap();
+            assert_eq!(contract.balance_of(AccountId::from([0x0; 32])), 69);
+        }
+
+        #[ink::test]
+        fn allowance_works() {
+            let mut contract = Token::new(4294967000);
+            assert_eq!(contract.balance_of(AccountId::from([0x1; 32])), 4294967000);
+            contract.approve(AccountId::from([0x2; 32]), 1000000).unwrap();
+            assert_eq!(contract.allowance(AccountId::from([0x1; 32]), AccountId::from([0x2; 32])), 1000000);
+        }
+
+        #[ink::test]
+        fn increase_and_decrease_allowance_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.increase_allowance(accounts.bob, 100).unwrap();
+            contract.increase_allowance(accounts.bob, 50).unwrap();
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 150);
+            contract.decrease_allowance(accounts.bob, 120).unwrap();
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 30);
+            assert_eq!(
+                contract.decrease_allowance(accounts.bob, 31),
+                Err(Error::InsufficientAllowance)
+            );
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 30);
+        }
+
+        #[ink::test]
+        fn supply_beyond_u32_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let supply = 1_000_000_000 * 10u128.pow(18);
+            let mut contract = Token::new(supply);
+            assert_eq!(contract.total_supply(), supply);
+            contract.transfer(accounts.bob, supply / 2).unwrap();
+            assert_eq!(contract.balance_of(accounts.bob), supply / 2);
+        }
+
+        #[ink::test]
+        fn default_metadata_works() {
+            let contract = Token::new(1000);
+            assert_eq!(contract.token_name(), None);
+            assert_eq!(contract.token_symbol(), None);
+            assert_eq!(contract.token_decimals(), 18);
+        }
+
+        #[ink::test]
+        fn metadata_works() {
+            let contract = Token::new_with_metadata(
+                1000,
+                Some(String::from("Token")),
+                Some(String::from("TOK")),
+                6,
+            );
+            assert_eq!(contract.token_name(), Some(String::from("Token")));
+            assert_eq!(contract.token_symbol(), Some(String::from("TOK")));
+            assert_eq!(contract.token_decimals(), 6);
+            assert_eq!(contract.total_supply(), 1000);
+        }
+
+        #[ink::test]
+        fn psp22_matches_inherent_messages() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            assert_eq!(PSP22::total_supply(&contract), contract.total_supply());
+
+            assert_eq!(PSP22::transfer(&mut contract, accounts.bob, 100, Vec::new()), Ok(()));
+            assert_eq!(PSP22::balance_of(&contract, accounts.bob), 100);
+            assert_eq!(PSP22::balance_of(&contract, accounts.bob), contract.balance_of(accounts.bob));
+
+            PSP22::approve(&mut contract, accounts.bob, 50).unwrap();
+            PSP22::increase_allowance(&mut contract, accounts.bob, 20).unwrap();
+            PSP22::decrease_allowance(&mut contract, accounts.bob, 10).unwrap();
+            assert_eq!(PSP22::allowance(&contract, accounts.alice, accounts.bob), 60);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                PSP22::transfer_from(&mut contract, accounts.alice, accounts.charlie, 60, Vec::new()),
+                Ok(())
+            );
+            assert_eq!(contract.balance_of(accounts.charlie), 60);
+            assert_eq!(
+                PSP22::transfer(&mut contract, accounts.charlie, 1000, Vec::new()),
+                Err(PSP22Error::InsufficientBalance)
+            );
+        }
+
+        #[ink::test]
+        fn pause_blocks_transfers() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.approve(accounts.bob, 10).unwrap();
+            assert!(!contract.paused());
+            contract.pause().unwrap();
+            assert!(contract.paused());
+            assert_eq!(contract.transfer(accounts.bob, 10), Err(Error::Paused));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.bob, 10),
+                Err(Error::Paused)
+            );
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            contract.unpause().unwrap();
+            assert_eq!(contract.transfer(accounts.bob, 10), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 10);
+        }
+
+        #[ink::test]
+        fn pause_by_non_owner_fails() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.pause(), Err(Error::NotOwner));
+            assert!(!contract.paused());
+        }
+
+        #[ink::test]
+        fn mint_up_to_cap_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_capped(1000, 1500);
+            assert_eq!(contract.cap(), 1500);
+            assert_eq!(contract.mint(accounts.bob, 500), Ok(()));
+            assert_eq!(contract.total_supply(), 1500);
+            assert_eq!(contract.mint(accounts.bob, 1), Err(Error::CapExceeded));
+            assert_eq!(contract.total_supply(), 1500);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "initial supply exceeds cap")]
+        fn new_capped_above_cap_fails() {
+            Token::new_capped(1001, 1000);
+        }
+
+        #[ink::test]
+        fn batch_transfer_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            assert_eq!(
+                contract.batch_transfer(
+                    vec![accounts.bob, accounts.charlie, accounts.django],
+                    vec![100, 200, 300],
+                ),
+                Ok(())
+            );
+            assert_eq!(contract.balance_of(accounts.alice), 400);
+            assert_eq!(contract.balance_of(accounts.bob), 100);
+            assert_eq!(contract.balance_of(accounts.charlie), 200);
+            assert_eq!(contract.balance_of(accounts.django), 300);
+            // One for the constructor plus one per recipient.
+            assert_eq!(ink_env::test::recorded_events().count(), 4);
+        }
+
+        #[ink::test]
+        fn batch_transfer_length_mismatch_fails() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            assert_eq!(
+                contract.batch_transfer(vec![accounts.bob, accounts.charlie], vec![100]),
+                Err(Error::LengthMismatch)
+            );
+            assert_eq!(
+                contract.batch_transfer(vec![accounts.bob, accounts.charlie], vec![600, 600]),
+                Err(Error::InsufficientBalance)
+            );
+            assert_eq!(contract.balance_of(accounts.alice), 1000);
+        }
+
+        #[ink::test]
+        fn zero_address_allowed_by_default() {
+            let mut contract = Token::new(1000);
+            assert_eq!(contract.transfer(AccountId::from([0x0; 32]), 10), Ok(()));
+            assert_eq!(contract.approve(AccountId::from([0x0; 32]), 10), Ok(()));
+        }
+
+        #[ink::test]
+        fn zero_address_rejected_when_enabled() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let zero = AccountId::from([0x0; 32]);
+            let mut contract = Token::new_with_zero_address_check(1000, true);
+            assert_eq!(contract.transfer(zero, 10), Err(Error::ZeroAddress));
+            assert_eq!(contract.approve(zero, 10), Err(Error::ZeroAddress));
+            assert_eq!(contract.mint(zero, 10), Err(Error::ZeroAddress));
+
+            contract.approve(accounts.bob, 10).unwrap();
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.transfer_from(accounts.alice, zero, 10), Err(Error::ZeroAddress));
+            assert_eq!(contract.balance_of(accounts.alice), 1000);
+        }
+
+        #[ink::test]
+        fn burn_from_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.approve(accounts.bob, 300).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.burn_from(accounts.alice, 200), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 100);
+            assert_eq!(contract.balance_of(accounts.alice), 800);
+            assert_eq!(contract.total_supply(), 800);
+        }
+
+        #[ink::test]
+        fn burn_from_insufficient_allowance_fails() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.approve(accounts.bob, 100).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.burn_from(accounts.alice, 101), Err(Error::InsufficientAllowance));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 100);
+            assert_eq!(contract.total_supply(), 1000);
+        }
+
+        #[ink::test]
+        fn minter_role_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_capped(1000, Balance::MAX);
+            assert!(!contract.is_minter(accounts.bob));
+            contract.grant_minter(accounts.bob).unwrap();
+            assert!(contract.is_minter(accounts.bob));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.mint(accounts.charlie, 100), Ok(()));
+            assert_eq!(contract.balance_of(accounts.charlie), 100);
+            assert_eq!(contract.grant_minter(accounts.charlie), Err(Error::NotOwner));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            contract.revoke_minter(accounts.bob).unwrap();
+            assert!(!contract.is_minter(accounts.bob));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.mint(accounts.charlie, 100), Err(Error::NotMinter));
+            assert_eq!(contract.total_supply(), 1100);
+        }
+
+        #[ink::test]
+        fn frozen_sender_cannot_transfer() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.transfer(accounts.bob, 100).unwrap();
+            contract.freeze_account(accounts.bob).unwrap();
+            assert!(contract.is_frozen(accounts.bob));
+            assert_eq!(contract.balance_of(accounts.bob), 100);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.transfer(accounts.charlie, 10), Err(Error::AccountFrozen));
+            assert_eq!(contract.unfreeze_account(accounts.bob), Err(Error::NotOwner));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            contract.unfreeze_account(accounts.bob).unwrap();
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.transfer(accounts.charlie, 10), Ok(()));
+        }
+
+        #[ink::test]
+        fn frozen_receiver_cannot_receive() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.freeze_account(accounts.bob).unwrap();
+            assert_eq!(contract.transfer(accounts.bob, 10), Err(Error::AccountFrozen));
+            assert_eq!(contract.transfer(accounts.charlie, 10), Ok(()));
+            assert_eq!(contract.balance_of(accounts.alice), 990);
+        }
+
+        #[ink::test]
+        fn upgrade_by_non_owner_fails() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.upgrade([0x42; 32]), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn safe_approve_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.approve(accounts.bob, 100).unwrap();
+            assert_eq!(contract.safe_approve(accounts.bob, 100, 50), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 50);
+        }
+
+        #[ink::test]
+        fn safe_approve_mismatch_fails() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.approve(accounts.bob, 100).unwrap();
+            assert_eq!(contract.safe_approve(accounts.bob, 90, 50), Err(Error::AllowanceChanged));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 100);
+        }
+
+        #[ink::test]
+        fn snapshot_preserves_historical_balances() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            assert_eq!(contract.balance_of_at(accounts.alice, 1), Err(Error::InvalidSnapshot));
+
+            assert_eq!(contract.snapshot(), Ok(1));
+            contract.transfer(accounts.bob, 100).unwrap();
+            contract.transfer(accounts.bob, 100).unwrap();
+            assert_eq!(contract.snapshot(), Ok(2));
+            contract.transfer(accounts.bob, 300).unwrap();
+
+            assert_eq!(contract.balance_of_at(accounts.alice, 1), Ok(1000));
+            assert_eq!(contract.balance_of_at(accounts.bob, 1), Ok(0));
+            assert_eq!(contract.balance_of_at(accounts.alice, 2), Ok(800));
+            assert_eq!(contract.balance_of_at(accounts.bob, 2), Ok(200));
+            assert_eq!(contract.balance_of_at(accounts.charlie, 2), Ok(0));
+            assert_eq!(contract.balance_of(accounts.alice), 500);
+        }
+
+        #[ink::test]
+        fn balance_of_at_finds_sparse_entries() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            // Bob's balance changes after snapshots 3, 7 and 8 only.
+            for id in 1..=10 {
+                assert_eq!(contract.snapshot(), Ok(id));
+                if matches!(id, 3 | 7 | 8) {
+                    contract.transfer(accounts.bob, 10 * id as Balance).unwrap();
+                }
+            }
+
+            let expected = [(1, 0), (3, 0), (4, 30), (7, 30), (8, 100), (9, 180), (10, 180)];
+            for (id, balance) in expected {
+                assert_eq!(contract.balance_of_at(accounts.bob, id), Ok(balance), "snapshot {}", id);
+            }
+            assert_eq!(contract.balance_of_at(accounts.alice, 5), Ok(970));
+            assert_eq!(contract.balance_of_at(accounts.alice, 8), Ok(900));
+        }
+
+        #[ink::test]
+        fn snapshot_by_non_owner_fails() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.snapshot(), Err(Error::NotOwner));
+            assert_eq!(contract.current_snapshot_id(), 0);
+        }
+
+        /// Returns the Substrate ECDSA account of `secret_key`.
+        fn ecdsa_account(secret_key: [u8; 32]) -> AccountId {
+            use secp256k1::{
+                PublicKey,
+                SecretKey,
+                SECP256K1,
+            };
+
+            let secret_key = SecretKey::from_slice(&secret_key).unwrap();
+            let public_key = PublicKey::from_secret_key(SECP256K1, &secret_key).serialize();
+            let mut account = [0u8; 32];
+            ink_env::hash_bytes::<ink_env::hash::Blake2x256>(&public_key, &mut account);
+            AccountId::from(account)
+        }
+
+        fn sign_permit(
+            contract: &Token,
+            secret_key: [u8; 32],
+            spender: AccountId,
+            value: Balance,
+            deadline: u64,
+        ) -> (AccountId, [u8; 65]) {
+            let owner = ecdsa_account(secret_key);
+            let nonce = contract.nonces.get(owner).unwrap_or_default();
+            let hash = contract.permit_hash(&owner, &spender, value, nonce, deadline);
+            (owner, sign_hash(secret_key, hash))
+        }
+
+        fn sign_hash(secret_key: [u8; 32], hash: [u8; 32]) -> [u8; 65] {
+            use secp256k1::{
+                Message,
+                SecretKey,
+                SECP256K1,
+            };
+
+            let secret_key = SecretKey::from_slice(&secret_key).unwrap();
+            let message = Message::from_slice(&hash).unwrap();
+            let (recovery_id, compact) = SECP256K1
+                .sign_ecdsa_recoverable(&message, &secret_key)
+                .serialize_compact();
+            let mut signature = [0u8; 65];
+            signature[..64].copy_from_slice(&compact);
+            signature[64] = recovery_id.to_i32() as u8;
+            signature
+        }
+
+        #[ink::test]
+        fn permit_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            let (owner, signature) = sign_permit(&contract, [0x11; 32], accounts.bob, 500, 100);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(contract.permit(owner, accounts.bob, 500, 100, signature), Ok(()));
+            assert_eq!(contract.allowance(owner, accounts.bob), 500);
+            // The nonce moved on, so the same signature cannot be replayed.
+            assert_eq!(
+                contract.permit(owner, accounts.bob, 500, 100, signature),
+                Err(Error::InvalidSignature)
+            );
+        }
+
+        #[ink::test]
+        fn permit_with_wrong_signer_fails() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            let (_, signature) = sign_permit(&contract, [0x11; 32], accounts.bob, 500, 100);
+            assert_eq!(
+                contract.permit(accounts.django, accounts.bob, 500, 100, signature),
+                Err(Error::InvalidSignature)
+            );
+            assert_eq!(contract.allowance(accounts.django, accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn expired_permit_fails() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            let (owner, signature) = sign_permit(&contract, [0x11; 32], accounts.bob, 500, 6);
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(
+                contract.permit(owner, accounts.bob, 500, 6, signature),
+                Err(Error::PermitExpired)
+            );
+            assert_eq!(contract.allowance(owner, accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn two_step_ownership_transfer_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.transfer_ownership(accounts.bob).unwrap();
+            assert_eq!(contract.pending_owner(), Some(accounts.bob));
+            // Alice stays in charge until Bob accepts.
+            assert_eq!(contract.pause(), Ok(()));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.accept_ownership(), Ok(()));
+            assert_eq!(contract.pending_owner(), None);
+            assert_eq!(contract.unpause(), Ok(()));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(contract.pause(), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn accept_ownership_by_non_pending_fails() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            assert_eq!(contract.accept_ownership(), Err(Error::NotPendingOwner));
+            contract.transfer_ownership(accounts.bob).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(contract.accept_ownership(), Err(Error::NotPendingOwner));
+            assert_eq!(contract.transfer_ownership(accounts.charlie), Err(Error::NotOwner));
+            assert_eq!(contract.pending_owner(), Some(accounts.bob));
+        }
+
+        #[ink::test]
+        fn renounce_ownership_works() {
+            let mut contract = Token::new(1000);
+            assert_eq!(contract.renounce_ownership(), Ok(()));
+            assert_eq!(contract.pause(), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn transfer_from_at_allowance_boundary() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.approve(accounts.bob, 100).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.bob, 101),
+                Err(Error::InsufficientAllowance)
+            );
+            assert_eq!(contract.transfer_from(accounts.alice, accounts.bob, 100), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 0);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.bob, 1),
+                Err(Error::InsufficientAllowance)
+            );
+        }
+
+        #[ink::test]
+        fn transfer_from_allowance_above_balance_fails() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.approve(accounts.bob, 2000).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.bob, 1001),
+                Err(Error::InsufficientBalance)
+            );
+            assert_eq!(contract.transfer_from(accounts.alice, accounts.bob, 1000), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 1000);
+        }
+
+        #[ink::test]
+        fn balances_of_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.transfer(accounts.charlie, 300).unwrap();
+            assert_eq!(
+                contract.balances_of(vec![accounts.charlie, accounts.bob, accounts.alice]),
+                vec![300, 0, 700]
+            );
+            assert_eq!(contract.balances_of(Vec::new()), Vec::<Balance>::new());
+        }
+
+        #[ink::test]
+        fn transfer_without_fee_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.set_fee(0, accounts.eve).unwrap();
+            contract.transfer(accounts.bob, 100).unwrap();
+            assert_eq!(contract.balance_of(accounts.bob), 100);
+            assert_eq!(contract.balance_of(accounts.eve), 0);
+            // Mint, collector and fee config changes and the transfer itself.
+            assert_eq!(ink_env::test::recorded_events().count(), 4);
+        }
+
+        #[ink::test]
+        fn transfer_with_fee_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.set_fee(250, accounts.eve).unwrap();
+            assert_eq!(contract.fee_bps(), 250);
+            assert_eq!(contract.fee_collector(), accounts.eve);
+
+            contract.transfer(accounts.bob, 400).unwrap();
+            assert_eq!(contract.balance_of(accounts.alice), 600);
+            assert_eq!(contract.balance_of(accounts.bob), 390);
+            assert_eq!(contract.balance_of(accounts.eve), 10);
+            assert_eq!(contract.total_supply(), 1000);
+            assert_eq!(ink_env::test::recorded_events().count(), 5);
+        }
+
+        #[ink::test]
+        fn transfer_fee_rounds_down() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.set_fee(250, accounts.eve).unwrap();
+            // 2.5% of 39 is 0.975, which rounds down to no fee at all.
+            contract.transfer(accounts.bob, 39).unwrap();
+            assert_eq!(contract.balance_of(accounts.bob), 39);
+            contract.transfer(accounts.bob, 40).unwrap();
+            assert_eq!(contract.balance_of(accounts.bob), 78);
+            assert_eq!(contract.balance_of(accounts.eve), 1);
+        }
+
+        #[ink::test]
+        fn set_fee_is_bounded_and_owner_only() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            assert_eq!(contract.set_fee(MAX_FEE_BPS + 1, accounts.eve), Err(Error::FeeTooHigh));
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.set_fee(100, accounts.eve), Err(Error::NotOwner));
+            assert_eq!(contract.fee_bps(), 0);
+        }
+
+        #[ink::test]
+        fn mint_emits_transfer_and_mint() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_capped(1000, Balance::MAX);
+            contract.mint(accounts.bob, 500).unwrap();
+
+            let events = recorded_events();
+            assert_eq!(events.len(), 3);
+            assert!(matches!(
+                &events[1],
+                Event::Transfer(Transfer { from: None, to: Some(to), value: 500, value_bucket: 2 }) if *to == accounts.bob
+            ));
+            assert!(matches!(
+                &events[2],
+                Event::Mint(Mint { to, value: 500 }) if *to == accounts.bob
+            ));
+        }
+
+        #[ink::test]
+        fn burn_emits_transfer_and_burn() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.burn(300).unwrap();
+
+            let events = recorded_events();
+            assert_eq!(events.len(), 3);
+            assert!(matches!(
+                &events[1],
+                Event::Transfer(Transfer { from: Some(from), to: None, value: 300, value_bucket: 2 }) if *from == accounts.alice
+            ));
+            assert!(matches!(
+                &events[2],
+                Event::Burn(Burn { from, value: 300, memo }) if *from == accounts.alice && *memo == [0; 32]
+            ));
+        }
+
+        #[ink::test]
+        fn reentrant_transfer_fails() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            let result = contract.with_guard(|contract| contract.transfer(accounts.bob, 10));
+            assert_eq!(result, Err(Error::Reentrancy));
+            assert_eq!(contract.balance_of(accounts.bob), 0);
+
+            // The guard is released afterwards.
+            assert_eq!(contract.transfer(accounts.bob, 10), Ok(()));
+        }
+
+        #[ink::test]
+        fn guard_is_visible_to_a_nested_call() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            let result = contract.with_guard(|_| {
+                // A call re-entering from another contract loads the plain
+                // fields as last stored, like this fresh instance; only what
+                // is already in storage tells it that a call is in progress.
+                let mut nested = Token::new(0);
+                assert_eq!(nested.transfer(accounts.bob, 0), Err(Error::Reentrancy));
+                assert_eq!(nested.simulate_transfer(accounts.alice, accounts.bob, 10), Err(Error::Reentrancy));
+                Ok(())
+            });
+            assert_eq!(result, Ok(()));
+            assert_eq!(contract.transfer(accounts.bob, 10), Ok(()));
+        }
+
+        #[ink::test]
+        fn transfer_with_data_to_plain_account_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            assert_eq!(contract.transfer_with_data(accounts.bob, 100, vec![0xde, 0xad]), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 100);
+            assert_eq!(contract.balance_of(accounts.alice), 900);
+            assert_eq!(contract.transfer_with_data(accounts.bob, 1000, Vec::new()), Err(Error::InsufficientBalance));
+        }
+
+        #[ink::test]
+        fn minted_and_burned_counters_track_supply() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_capped(1000, Balance::MAX);
+            contract.mint(accounts.bob, 500).unwrap();
+            contract.burn(200).unwrap();
+            contract.mint(accounts.alice, 50).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            contract.burn(500).unwrap();
+
+            assert_eq!(contract.total_minted(), 1550);
+            assert_eq!(contract.total_burned(), 700);
+            assert_eq!(
+                contract.total_supply(),
+                contract.total_minted() - contract.total_burned()
+            );
+        }
+
+        #[ink::test]
+        fn unlimited_allowance_is_not_decremented() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.approve(accounts.bob, UNLIMITED_ALLOWANCE).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            for _ in 0..3 {
+                contract.transfer_from(accounts.alice, accounts.charlie, 100).unwrap();
+            }
+            assert_eq!(contract.balance_of(accounts.charlie), 300);
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), UNLIMITED_ALLOWANCE);
+        }
+
+        #[ink::test]
+        fn locked_tokens_cannot_move_until_unlock() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.transfer(accounts.bob, 500).unwrap();
+            contract.set_lock(accounts.bob, 400, 12).unwrap();
+            assert_eq!(contract.lock_of(accounts.bob), Some((400, 12)));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.transfer(accounts.charlie, 100), Ok(()));
+            assert_eq!(contract.transfer(accounts.charlie, 1), Err(Error::TokensLocked));
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.transfer(accounts.charlie, 1), Err(Error::TokensLocked));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.transfer(accounts.charlie, 400), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn set_lock_by_non_owner_fails() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.set_lock(accounts.bob, 400, 12), Err(Error::NotOwner));
+            assert_eq!(contract.lock_of(accounts.bob), None);
+        }
+
+        #[ink::test]
+        fn linear_vesting_releases_over_time() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let contract_id = set_contract_account();
+            let mut contract = Token::new(1000);
+            contract.create_vesting(accounts.bob, 400, 0, 12).unwrap();
+            assert_eq!(contract.balance_of(accounts.alice), 600);
+            assert_eq!(contract.balance_of(contract_id), 400);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.release(), Err(Error::NothingToRelease));
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.release(), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 200);
+            assert_eq!(contract.release(), Err(Error::NothingToRelease));
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.release(), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 400);
+            assert_eq!(contract.balance_of(contract_id), 0);
+            assert_eq!(contract.vesting_of(accounts.bob).unwrap().released, 400);
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.release(), Err(Error::NothingToRelease));
+        }
+
+        #[ink::test]
+        fn create_vesting_checks_owner_and_duplicates() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            set_contract_account();
+            let mut contract = Token::new(1000);
+            contract.create_vesting(accounts.bob, 400, 0, 12).unwrap();
+            assert_eq!(
+                contract.create_vesting(accounts.bob, 100, 0, 12),
+                Err(Error::VestingAlreadyExists)
+            );
+            assert_eq!(
+                contract.create_vesting(accounts.charlie, 601, 0, 12),
+                Err(Error::InsufficientBalance)
+            );
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                contract.create_vesting(accounts.charlie, 100, 0, 12),
+                Err(Error::NotOwner)
+            );
+        }
+
+        #[ink::test]
+        fn constructor_mints_initial_supply_once() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let contract = Token::new(1000);
+            assert_eq!(contract.owner, accounts.alice);
+            assert_eq!(contract.balance_of(accounts.alice), 1000);
+            assert_eq!(contract.total_supply(), 1000);
+
+            // `new_init` is private: the only path into it is a constructor, so
+            // the initial mint happens exactly once per deployment.
+            let events = recorded_events();
+            assert_eq!(events.len(), 1);
+            assert!(matches!(
+                &events[0],
+                Event::Transfer(Transfer { from: None, to: Some(to), value: 1000, value_bucket: 3 }) if *to == accounts.alice
+            ));
+        }
+
+        #[ink::test]
+        fn self_transfer_keeps_balance() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.set_fee(100, accounts.eve).unwrap();
+            assert_eq!(contract.transfer(accounts.alice, 1000), Ok(()));
+            assert_eq!(contract.balance_of(accounts.alice), 1000);
+            assert_eq!(contract.balance_of(accounts.eve), 0);
+            assert_eq!(contract.transfer(accounts.alice, 1001), Err(Error::InsufficientBalance));
+            assert_eq!(recorded_events().len(), 4);
+        }
+
+        #[ink::test]
+        fn describe_error_covers_every_variant() {
+            let contract = Token::new(1000);
+            let mut descriptions = Vec::new();
+            for code in 0..=u8::MAX {
+                let error = match <Error as scale::Decode>::decode(&mut &[code][..]) {
+                    Ok(error) => error,
+                    Err(_) => break,
+                };
+                assert_eq!(scale::Encode::encode(&error), vec![code]);
+                let description = contract.describe_error(code).unwrap();
+                assert_eq!(description, error.message());
+                assert!(!descriptions.contains(&description));
+                descriptions.push(description);
+            }
+
+            assert_eq!(contract.describe_error(0), Some(String::from("insufficient balance")));
+            assert_eq!(contract.describe_error(descriptions.len() as u8), None);
+        }
+
+        #[ink::test]
+        fn faucet_claim_respects_cooldown() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_with_faucet(1000, 50, 12);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.claim(), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 50);
+            assert_eq!(contract.claim(), Err(Error::CooldownActive));
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.claim(), Err(Error::CooldownActive));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.claim(), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 100);
+            assert_eq!(contract.total_supply(), 1100);
+
+            // The cooldown is tracked per account.
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(contract.claim(), Ok(()));
+        }
+
+        #[ink::test]
+        fn faucet_disabled_by_default() {
+            let mut contract = Token::new(1000);
+            assert_eq!(contract.claim(), Err(Error::FaucetDisabled));
+        }
+
+        #[ink::test]
+        fn dividends_are_proportional_to_balance() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let contract_id = set_contract_account();
+            let mut contract = Token::new(1100);
+            contract.transfer(accounts.bob, 300).unwrap();
+            contract.transfer(accounts.charlie, 600).unwrap();
+            contract.deposit_dividends(100).unwrap();
+            assert_eq!(contract.balance_of(contract_id), 100);
+
+            assert_eq!(contract.dividends_of(accounts.alice), Ok(10));
+            assert_eq!(contract.dividends_of(accounts.bob), Ok(30));
+            assert_eq!(contract.dividends_of(accounts.charlie), Ok(60));
+
+            // Moving tokens afterwards does not move already accrued dividends.
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            contract.transfer(accounts.charlie, 100).unwrap();
+            assert_eq!(contract.dividends_of(accounts.bob), Ok(30));
+            assert_eq!(contract.dividends_of(accounts.charlie), Ok(60));
+
+            assert_eq!(contract.withdraw_dividends(), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 230);
+            assert_eq!(contract.dividends_of(accounts.bob), Ok(0));
+            assert_eq!(contract.withdraw_dividends(), Err(Error::NoDividends));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(contract.withdraw_dividends(), Ok(()));
+            assert_eq!(contract.balance_of(accounts.charlie), 760);
+            assert_eq!(contract.balance_of(contract_id), 10);
+        }
+
+        #[ink::test]
+        fn deposit_dividends_by_non_owner_fails() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            set_contract_account();
+            let mut contract = Token::new(1000);
+            contract.transfer(accounts.bob, 100).unwrap();
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.deposit_dividends(50), Err(Error::NotOwner));
+            assert_eq!(contract.dividends_of(accounts.bob), Ok(0));
+        }
+
+        #[ink::test]
+        fn oversized_dividend_figures_fail_instead_of_saturating() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            set_contract_account();
+            let mut contract = Token::new_capped(1100, Balance::MAX);
+            contract.transfer(accounts.bob, 300).unwrap();
+            contract.deposit_dividends(100).unwrap();
+
+            assert_eq!(contract.mint(accounts.charlie, Balance::MAX / 2), Err(Error::Overflow));
+            assert_eq!(contract.balance_of(accounts.charlie), 0);
+            assert_eq!(contract.total_supply(), 1100);
+            assert_eq!(contract.dividends_of(accounts.bob), Ok(30));
+        }
+
+        #[ink::test]
+        fn zero_initial_supply_emits_nothing() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_capped(0, Balance::MAX);
+            assert_eq!(contract.total_supply(), 0);
+            assert_eq!(contract.balance_of(accounts.alice), 0);
+            assert_eq!(recorded_events().len(), 0);
+
+            contract.mint(accounts.bob, 10).unwrap();
+            assert_eq!(contract.total_supply(), 10);
+        }
+
+        #[ink::test]
+        fn deposit_mints_wrapped_tokens() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_capped(0, Balance::MAX);
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(250);
+            assert_eq!(contract.deposit(), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 250);
+            assert_eq!(contract.total_supply(), 250);
+
+            let mut fixed = Token::new(0);
+            assert_eq!(fixed.deposit(), Err(Error::CapExceeded));
+        }
+
+        #[ink::test]
+        fn withdraw_burns_wrapped_tokens() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let contract_id = set_contract_account();
+            let mut contract = Token::new_capped(0, Balance::MAX);
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(contract_id, 250);
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(250);
+            contract.deposit().unwrap();
+
+            assert_eq!(contract.withdraw(251), Err(Error::InsufficientBalance));
+            let native_before =
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(accounts.bob).unwrap();
+            assert_eq!(contract.withdraw(100), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 150);
+            assert_eq!(contract.total_supply(), 150);
+            assert_eq!(
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(accounts.bob),
+                Ok(native_before + 100)
+            );
+            assert_eq!(
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(contract_id),
+                Ok(150)
+            );
+        }
+
+        #[ink::test]
+        fn approve_batch_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            assert_eq!(
+                contract.approve_batch(
+                    vec![accounts.bob, accounts.charlie, accounts.django],
+                    vec![10, 20, 30],
+                ),
+                Ok(())
+            );
+            assert_eq!(
+                contract.allowances_of(vec![
+                    (accounts.alice, accounts.bob),
+                    (accounts.alice, accounts.charlie),
+                    (accounts.alice, accounts.django),
+                    (accounts.bob, accounts.alice),
+                ]),
+                vec![10, 20, 30, 0]
+            );
+            // One for the constructor plus one `Approval` per spender.
+            assert_eq!(recorded_events().len(), 4);
+        }
+
+        #[ink::test]
+        fn approve_batch_length_mismatch_fails() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            assert_eq!(
+                contract.approve_batch(vec![accounts.bob, accounts.charlie], vec![10]),
+                Err(Error::LengthMismatch)
+            );
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn max_tx_amount_limits_transfers() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.transfer(accounts.bob, 500).unwrap();
+            contract.set_max_tx_amount(Some(100)).unwrap();
+            assert_eq!(contract.max_tx_amount(), Some(100));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.transfer(accounts.charlie, 100), Ok(()));
+            assert_eq!(contract.transfer(accounts.charlie, 101), Err(Error::ExceedsMaxTx));
+            assert_eq!(contract.set_max_tx_amount(None), Err(Error::NotOwner));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(contract.transfer(accounts.charlie, 300), Ok(()));
+            contract.grant_minter(accounts.bob).unwrap();
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.transfer(accounts.charlie, 101), Ok(()));
+        }
+
+        #[ink::test]
+        fn max_wallet_caps_recipient_balance() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.set_max_wallet(Some(300)).unwrap();
+            assert_eq!(contract.max_wallet(), Some(300));
+
+            assert_eq!(contract.transfer(accounts.bob, 300), Ok(()));
+            assert_eq!(contract.transfer(accounts.bob, 1), Err(Error::ExceedsMaxWallet));
+            assert_eq!(contract.balance_of(accounts.bob), 300);
+        }
+
+        #[ink::test]
+        fn max_wallet_exempts_owner_and_fee_collector() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(10_000);
+            contract.transfer(accounts.bob, 5000).unwrap();
+            contract.set_timelock_delay(6).unwrap();
+            contract
+                .queue_action(ACTION_SET_FEE, params_hash(&(1000u16, accounts.eve)))
+                .unwrap();
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            contract.set_fee(1000, accounts.eve).unwrap();
+            contract.set_max_wallet(Some(300)).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            // Only the 300 net of fees lands in Charlie's wallet.
+            assert_eq!(contract.transfer(accounts.charlie, 333), Ok(()));
+            assert_eq!(contract.balance_of(accounts.charlie), 300);
+            assert_eq!(contract.transfer(accounts.eve, 4000), Ok(()));
+            assert_eq!(contract.transfer(accounts.alice, 500), Ok(()));
+            assert_eq!(contract.balance_of(accounts.eve), 3600 + 33 + 400 + 50);
+        }
+
+        #[ink::test]
+        fn transfer_from_emits_updated_approval() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.approve(accounts.bob, 100).unwrap();
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            contract.transfer_from(accounts.alice, accounts.charlie, 40).unwrap();
+
+            let events = recorded_events();
+            assert_eq!(events.len(), 4);
+            assert!(matches!(
+                &events[3],
+                Event::Approval(Approval { owner, spender, value: 60, value_bucket: 1 })
+                    if *owner == accounts.alice && *spender == accounts.bob
+            ));
+        }
+
+        #[ink::test]
+        fn unlimited_transfer_from_emits_no_approval() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.approve(accounts.bob, UNLIMITED_ALLOWANCE).unwrap();
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            contract.transfer_from(accounts.alice, accounts.charlie, 40).unwrap();
+
+            let events = recorded_events();
+            assert_eq!(events.len(), 3);
+            assert!(matches!(&events[2], Event::Transfer(_)));
+        }
+
+        #[ink::test]
+        fn total_holders_tracks_nonzero_balances() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_capped(1000, Balance::MAX);
+            assert_eq!(contract.total_holders(), 1);
+
+            contract.transfer(accounts.bob, 100).unwrap();
+            contract.transfer(accounts.bob, 100).unwrap();
+            contract.mint(accounts.charlie, 50).unwrap();
+            assert_eq!(contract.total_holders(), 3);
+
+            contract.transfer(accounts.alice, 800).unwrap();
+            contract.transfer(accounts.django, 0).unwrap();
+            assert_eq!(contract.total_holders(), 3);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            contract.transfer(accounts.charlie, 200).unwrap();
+            assert_eq!(contract.total_holders(), 2);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+            contract.burn(250).unwrap();
+            assert_eq!(contract.total_holders(), 1);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            contract.transfer(accounts.bob, 800).unwrap();
+            assert_eq!(contract.total_holders(), 1);
+            assert_eq!(contract.balance_of(accounts.bob), 800);
+        }
+
+        #[ink::test]
+        fn approved_pairs_are_logged_once() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.approve(accounts.bob, 10).unwrap();
+            contract.approve(accounts.charlie, 10).unwrap();
+            contract.approve(accounts.bob, 20).unwrap();
+            contract.increase_allowance(accounts.bob, 5).unwrap();
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            contract.increase_allowance(accounts.alice, 5).unwrap();
+
+            assert_eq!(contract.approved_pairs_len(), 3);
+            assert_eq!(
+                contract.approved_pairs(0, 10),
+                vec![
+                    (accounts.alice, accounts.bob),
+                    (accounts.alice, accounts.charlie),
+                    (accounts.bob, accounts.alice),
+                ]
+            );
+            assert_eq!(contract.approved_pairs(1, 1), vec![(accounts.alice, accounts.charlie)]);
+            assert_eq!(contract.approved_pairs(3, u32::MAX), vec![]);
+        }
+
+        #[ink::test]
+        fn rescue_self_balance_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let contract_id = set_contract_account();
+            let mut contract = Token::new_with_recovery_authority(1000, accounts.frank);
+            contract.transfer(contract_id, 300).unwrap();
+            assert_eq!(contract.rescue_self_balance(accounts.bob), Err(Error::NotRecoveryAuthority));
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.rescue_self_balance(accounts.bob), Err(Error::NotRecoveryAuthority));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.frank);
+            assert_eq!(contract.rescue_self_balance(accounts.bob), Ok(()));
+            assert_eq!(contract.balance_of(contract_id), 0);
+            assert_eq!(contract.balance_of(accounts.bob), 300);
+        }
+
+        #[ink::test]
+        fn rescue_self_balance_keeps_escrow() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let contract_id = set_contract_account();
+            let mut contract = Token::new_with_recovery_authority(1000, accounts.frank);
+            contract.create_vesting(accounts.bob, 400, 0, 12).unwrap();
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.frank);
+            assert_eq!(contract.rescue_self_balance(accounts.charlie), Err(Error::InsufficientBalance));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            contract.transfer(contract_id, 50).unwrap();
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.frank);
+            assert_eq!(contract.rescue_self_balance(accounts.charlie), Ok(()));
+            assert_eq!(contract.balance_of(accounts.charlie), 50);
+            assert_eq!(contract.balance_of(contract_id), 400);
+
+            // A forced transfer cannot take tokens out of custody either.
+            assert_eq!(
+                contract.force_transfer(contract_id, accounts.django, 100),
+                Err(Error::InsufficientBalance)
+            );
+            assert_eq!(contract.balance_of(contract_id), 400);
+            assert_eq!(contract.balance_of(accounts.django), 0);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            contract.transfer(contract_id, 30).unwrap();
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.frank);
+            assert_eq!(
+                contract.force_transfer(contract_id, accounts.django, 31),
+                Err(Error::InsufficientBalance)
+            );
+            assert_eq!(contract.force_transfer(contract_id, accounts.django, 30), Ok(()));
+            assert_eq!(contract.balance_of(contract_id), 400);
+        }
+
+        #[ink::test]
+        fn transfer_from_by_deadline_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.approve(accounts.bob, 100).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.transfer_from_by_deadline(accounts.alice, accounts.bob, 50, 6), Ok(()));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(
+                contract.transfer_from_by_deadline(accounts.alice, accounts.bob, 50, 6),
+                Err(Error::Expired)
+            );
+            assert_eq!(contract.balance_of(accounts.bob), 50);
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 50);
+        }
+
+        #[ink::test]
+        fn transfer_units_scales_by_decimals() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_with_metadata(1000, None, None, 2);
+            assert_eq!(contract.transfer_units(accounts.bob, 1, 50), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 150);
+            assert_eq!(contract.transfer_units(accounts.bob, 1, 150), Err(Error::InvalidFraction));
+            assert_eq!(contract.transfer_units(accounts.bob, 9, 0), Err(Error::InsufficientBalance));
+            assert_eq!(contract.balance_of(accounts.bob), 150);
+        }
+
+        #[ink::test]
+        fn cap_reached_is_emitted_once() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_capped(1000, 1500);
+            contract.mint(accounts.bob, 499).unwrap();
+            assert!(!recorded_events().iter().any(|event| matches!(event, Event::CapReached(_))));
+
+            contract.mint(accounts.bob, 1).unwrap();
+            contract.burn(10).unwrap();
+            contract.mint(accounts.bob, 10).unwrap();
+            let cap_events = recorded_events()
+                .into_iter()
+                .filter(|event| matches!(event, Event::CapReached(CapReached { cap: 1500 })))
+                .count();
+            assert_eq!(cap_events, 1);
+            assert_eq!(contract.mint(accounts.bob, 1), Err(Error::CapExceeded));
+        }
+
+        #[ink::test]
+        fn force_transfer_moves_without_allowance() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_with_recovery_authority(1000, accounts.frank);
+            contract.transfer(accounts.bob, 300).unwrap();
+            contract.freeze_account(accounts.bob).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.frank);
+            assert_eq!(contract.force_transfer(accounts.bob, accounts.charlie, 200), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 100);
+            assert_eq!(contract.balance_of(accounts.charlie), 200);
+            assert!(matches!(
+                recorded_events().last(),
+                Some(Event::ForcedTransfer(ForcedTransfer { value: 200, .. }))
+            ));
+            assert_eq!(
+                contract.force_transfer(accounts.bob, accounts.charlie, 101),
+                Err(Error::InsufficientBalance)
+            );
+        }
+
+        #[ink::test]
+        fn force_transfer_is_recovery_authority_only() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                contract.force_transfer(accounts.alice, accounts.bob, 1),
+                Err(Error::NotRecoveryAuthority)
+            );
+        }
+
+        #[ink::test]
+        fn approve_and_call_requires_contract_spender() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            assert_eq!(
+                contract.approve_and_call(accounts.bob, 100, [0xde, 0xad, 0xbe, 0xef], Vec::new()),
+                Err(Error::CallFailed)
+            );
+            // On-chain the error reverts the approval; off-chain the write is visible.
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 100);
+        }
+
+        #[ink::test]
+        fn circulating_supply_skips_excluded_accounts() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.transfer(accounts.bob, 300).unwrap();
+            assert_eq!(contract.circulating_supply(), 1000);
+
+            contract.exclude_from_circulation(accounts.alice).unwrap();
+            contract.exclude_from_circulation(accounts.alice).unwrap();
+            assert_eq!(contract.circulating_supply(), 300);
+            assert_eq!(contract.total_supply(), 1000);
+
+            contract.include_in_circulation(accounts.alice).unwrap();
+            assert_eq!(contract.circulating_supply(), 1000);
+        }
+
+        #[ink::test]
+        fn including_an_account_keeps_the_others_excluded() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.transfer(accounts.bob, 100).unwrap();
+            contract.transfer(accounts.charlie, 200).unwrap();
+            contract.transfer(accounts.django, 300).unwrap();
+            contract.exclude_from_circulation(accounts.bob).unwrap();
+            contract.exclude_from_circulation(accounts.charlie).unwrap();
+            contract.exclude_from_circulation(accounts.django).unwrap();
+            assert_eq!(contract.circulating_supply(), 400);
+
+            // Removing from the middle moves the last entry into its slot.
+            contract.include_in_circulation(accounts.bob).unwrap();
+            assert_eq!(contract.circulating_supply(), 500);
+            contract.include_in_circulation(accounts.bob).unwrap();
+            assert_eq!(contract.circulating_supply(), 500);
+            contract.include_in_circulation(accounts.django).unwrap();
+            assert_eq!(contract.circulating_supply(), 800);
+            contract.exclude_from_circulation(accounts.bob).unwrap();
+            assert_eq!(contract.circulating_supply(), 700);
+            contract.include_in_circulation(accounts.charlie).unwrap();
+            contract.include_in_circulation(accounts.bob).unwrap();
+            assert_eq!(contract.circulating_supply(), 1000);
+        }
+
+        fn blake2x256<T: scale::Encode>(input: &T) -> [u8; 32] {
+            let mut output = [0u8; 32];
+            ink_env::hash_encoded::<ink_env::hash::Blake2x256, _>(input, &mut output);
+            output
+        }
+
+        /// Returns `(root, bob_leaf, charlie_leaf)` for bob=100, charlie=250.
+        fn two_leaf_tree(
+            accounts: &ink_env::test::DefaultAccounts<ink_env::DefaultEnvironment>,
+        ) -> ([u8; 32], [u8; 32], [u8; 32]) {
+            let bob_leaf = blake2x256(&(accounts.bob, 100 as Balance));
+            let charlie_leaf = blake2x256(&(accounts.charlie, 250 as Balance));
+            let root = if bob_leaf <= charlie_leaf {
+                blake2x256(&(bob_leaf, charlie_leaf))
+            } else {
+                blake2x256(&(charlie_leaf, bob_leaf))
+            };
+            (root, bob_leaf, charlie_leaf)
+        }
+
+        #[ink::test]
+        fn claim_airdrop_with_valid_proof() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_capped(1000, Balance::MAX);
+            let (root, bob_leaf, charlie_leaf) = two_leaf_tree(&accounts);
+            contract.set_merkle_root(root).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.claim_airdrop(100, vec![charlie_leaf]), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 100);
+            assert_eq!(contract.claim_airdrop(100, vec![charlie_leaf]), Err(Error::AlreadyClaimed));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(contract.claim_airdrop(250, vec![bob_leaf]), Ok(()));
+            assert_eq!(contract.total_supply(), 1350);
+        }
+
+        #[ink::test]
+        fn claim_airdrop_rejects_invalid_proof() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            let (root, bob_leaf, charlie_leaf) = two_leaf_tree(&accounts);
+            contract.set_merkle_root(root).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.claim_airdrop(101, vec![charlie_leaf]), Err(Error::InvalidProof));
+            assert_eq!(contract.claim_airdrop(100, vec![bob_leaf]), Err(Error::InvalidProof));
+            assert_eq!(contract.claim_airdrop(100, Vec::new()), Err(Error::InvalidProof));
+            assert_eq!(contract.balance_of(accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn value_bucket_is_order_of_magnitude() {
+            assert_eq!(value_bucket(0), 0);
+            assert_eq!(value_bucket(9), 0);
+            assert_eq!(value_bucket(10), 1);
+            assert_eq!(value_bucket(999), 2);
+            assert_eq!(value_bucket(1_000_000), 6);
+            assert_eq!(value_bucket(Balance::MAX), 38);
+
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.transfer(accounts.bob, 250).unwrap();
+            assert!(matches!(
+                recorded_events().last(),
+                Some(Event::Transfer(Transfer { value: 250, value_bucket: 2, .. }))
+            ));
+        }
+
+        #[ink::test]
+        fn invalidate_nonce_cancels_signed_permit() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            let (owner, signature) = sign_permit(&contract, [0x11; 32], accounts.bob, 500, 100);
+            assert_eq!(contract.nonce_of(owner), 0);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(owner);
+            assert_eq!(contract.invalidate_nonce(), Ok(()));
+            assert_eq!(contract.nonce_of(owner), 1);
+            assert!(matches!(
+                recorded_events().last(),
+                Some(Event::NonceInvalidated(NonceInvalidated { nonce: 0, .. }))
+            ));
+            assert_eq!(
+                contract.permit(owner, accounts.bob, 500, 100, signature),
+                Err(Error::InvalidSignature)
+            );
+            assert_eq!(contract.allowance(owner, accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn min_transfer_rejects_dust() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            assert_eq!(contract.transfer(accounts.bob, 1), Ok(()));
+
+            contract.set_min_transfer(10).unwrap();
+            assert_eq!(contract.transfer(accounts.bob, 9), Err(Error::BelowMinimum));
+            assert_eq!(contract.transfer(accounts.bob, 10), Ok(()));
+            assert_eq!(contract.transfer(accounts.bob, 0), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 11);
+
+            contract.set_min_transfer(0).unwrap();
+            assert_eq!(contract.transfer(accounts.bob, 1), Ok(()));
+        }
+
+        #[ink::test]
+        fn approve_rejects_self_approval() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            assert_eq!(contract.approve(accounts.bob, 10), Ok(()));
+            assert!(matches!(
+                recorded_events().last(),
+                Some(Event::Approval(Approval { value: 10, .. }))
+            ));
+
+            assert_eq!(contract.approve(accounts.alice, 10), Err(Error::SelfApproval));
+            assert_eq!(contract.allowance(accounts.alice, accounts.alice), 0);
+        }
+
+        #[ink::test]
+        fn metadata_bundles_getters() {
+            let contract = Token::new_with_metadata(1000, Some(String::from("Token")), Some(String::from("TKN")), 6);
+            assert_eq!(
+                contract.metadata(),
+                (
+                    contract.token_name(),
+                    contract.token_symbol(),
+                    contract.token_decimals(),
+                    contract.total_supply()
+                )
+            );
+            assert_eq!(contract.metadata(), (Some(String::from("Token")), Some(String::from("TKN")), 6, 1000));
+        }
+
+        #[ink::test]
+        fn stake_and_unstake_with_reward() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_capped(2_000_000_000_000, Balance::MAX);
+            contract.transfer(accounts.bob, 1_000_000_000_000).unwrap();
+            contract.set_stake_reward_bps(10_000).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.unstake(), Err(Error::NothingStaked));
+            assert_eq!(contract.stake(1_000_000_000_001), Err(Error::InsufficientBalance));
+            assert_eq!(contract.stake(1_000_000_000_000), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 0);
+            assert_eq!(contract.staked_of(accounts.bob), Some((1_000_000_000_000, 0)));
+
+            // 12ms at 100% per day.
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.unstake(), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 1_000_000_138_888);
+            assert_eq!(contract.total_supply(), 2_000_000_138_888);
+            assert_eq!(contract.staked_of(accounts.bob), None);
+        }
+
+        #[ink::test]
+        fn stake_reward_is_limited_by_cap() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_capped(1_000_000_000_000, 1_000_000_000_100);
+            contract.set_stake_reward_bps(10_000).unwrap();
+            contract.transfer(accounts.bob, 1_000_000_000_000).unwrap();
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            contract.stake(1_000_000_000_000).unwrap();
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(contract.unstake(), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 1_000_000_000_100);
+            assert_eq!(contract.total_supply(), contract.cap());
+        }
+
+        #[ink::test]
+        fn unstake_while_mint_paused_returns_principal() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_capped(2_000_000_000_000, Balance::MAX);
+            contract.transfer(accounts.bob, 1_000_000_000_000).unwrap();
+            contract.set_stake_reward_bps(10_000).unwrap();
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            contract.stake(1_000_000_000_000).unwrap();
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            contract.set_mint_paused(true).unwrap();
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.claim_stake_reward(), Err(Error::NothingOwed));
+            assert_eq!(contract.unstake(), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 1_000_000_000_000);
+            assert_eq!(contract.staked_of(accounts.bob), None);
+            assert_eq!(contract.owed_stake_reward(accounts.bob), 138_888);
+            assert_eq!(contract.total_supply(), 2_000_000_000_000);
+            assert_eq!(contract.claim_stake_reward(), Err(Error::Paused));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            contract.set_mint_paused(false).unwrap();
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.claim_stake_reward(), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 1_000_000_138_888);
+            assert_eq!(contract.owed_stake_reward(accounts.bob), 0);
+            assert_eq!(contract.claim_stake_reward(), Err(Error::NothingOwed));
+        }
+
+        #[ink::test]
+        fn mint_pause_is_independent_of_transfer_pause() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_capped(1000, Balance::MAX);
+            contract.set_mint_paused(true).unwrap();
+            assert!(contract.paused());
+            assert_eq!(contract.mint(accounts.bob, 10), Err(Error::Paused));
+            assert_eq!(contract.transfer(accounts.bob, 10), Ok(()));
+
+            contract.set_mint_paused(false).unwrap();
+            contract.set_transfers_paused(true).unwrap();
+            assert_eq!(contract.transfer(accounts.bob, 10), Err(Error::Paused));
+            assert_eq!(contract.mint(accounts.bob, 10), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 20);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.set_mint_paused(true), Err(Error::NotOwner));
+            assert_eq!(contract.set_transfers_paused(false), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn new_whole_tokens_scales_supply() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let contract = Token::new_whole_tokens(1_000, 6);
+            assert_eq!(contract.total_supply(), 1_000_000_000);
+            assert_eq!(contract.balance_of(accounts.alice), 1_000_000_000);
+            assert_eq!(contract.token_decimals(), 6);
+
+            // With 18 decimals the largest representable supply is about 3.4 * 10^20 whole tokens.
+            let max_whole = Balance::MAX / 10u128.pow(18);
+            assert_eq!(Token::new_whole_tokens(max_whole, 18).total_supply(), max_whole * 10u128.pow(18));
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "whole supply overflows after decimals scaling")]
+        fn new_whole_tokens_overflow_fails() {
+            Token::new_whole_tokens(Balance::MAX / 10u128.pow(18) + 1, 18);
+        }
+
+        #[ink::test]
+        fn new_with_min_supply_accepts_enough_supply() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let contract = Token::new_with_min_supply(1000, 1000);
+            assert_eq!(contract.balance_of(accounts.alice), 1000);
+            assert_eq!(contract.cap(), 1000);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "initial supply below minimum")]
+        fn new_with_min_supply_rejects_zero_supply() {
+            Token::new_with_min_supply(0, 1);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "initial supply below minimum")]
+        fn new_with_min_supply_rejects_small_supply() {
+            Token::new_with_min_supply(999, 1000);
+        }
+
+        #[ink::test]
+        fn set_fee_emits_config_changed() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.set_fee(100, accounts.eve).unwrap();
+            contract.set_fee(250, accounts.eve).unwrap();
+            match recorded_events().last() {
+                Some(Event::ConfigChanged(ConfigChanged { key, old, new, by })) => {
+                    assert_eq!(key, b"fee_bps\0");
+                    assert_eq!((*old, *new), (100, 250));
+                    assert_eq!(*by, accounts.alice);
+                }
+                _ => panic!("expected a ConfigChanged event"),
+            }
+
+            contract.set_max_wallet(Some(500)).unwrap();
+            assert!(matches!(
+                recorded_events().last(),
+                Some(Event::ConfigChanged(ConfigChanged { old: u64::MAX, new: 500, .. }))
+            ));
+        }
+
+        #[ink::test]
+        fn transfer_from_and_burn_from_spend_alike() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.approve(accounts.bob, 100).unwrap();
+            contract.approve(accounts.charlie, UNLIMITED_ALLOWANCE).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            contract.transfer_from(accounts.alice, accounts.bob, 30).unwrap();
+            contract.burn_from(accounts.alice, 30).unwrap();
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 40);
+            assert_eq!(contract.transfer_from(accounts.alice, accounts.bob, 41), Err(Error::InsufficientAllowance));
+            assert_eq!(contract.burn_from(accounts.alice, 41), Err(Error::InsufficientAllowance));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+            contract.transfer_from(accounts.alice, accounts.charlie, 30).unwrap();
+            contract.burn_from(accounts.alice, 30).unwrap();
+            assert_eq!(contract.allowance(accounts.alice, accounts.charlie), UNLIMITED_ALLOWANCE);
+            let approvals = recorded_events()
+                .into_iter()
+                .filter(|event| matches!(event, Event::Approval(Approval { value: 70 | 40, .. })))
+                .count();
+            assert_eq!(approvals, 2);
+        }
+
+        #[ink::test]
+        fn coupon_redeems_once() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_capped(1000, Balance::MAX);
+            let mut code_hash = [0u8; 32];
+            ink_env::hash_bytes::<ink_env::hash::Blake2x256>(b"WELCOME", &mut code_hash);
+            contract.create_coupon(code_hash, 50).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.create_coupon(code_hash, 500), Err(Error::NotOwner));
+            assert_eq!(contract.redeem_coupon(b"welcome".to_vec()), Err(Error::InvalidCoupon));
+            assert_eq!(contract.redeem_coupon(b"WELCOME".to_vec()), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 50);
+            assert_eq!(contract.redeem_coupon(b"WELCOME".to_vec()), Err(Error::InvalidCoupon));
+            assert_eq!(contract.total_supply(), 1050);
+        }
+
+        #[ink::test]
+        fn transfer_all_empties_sender() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.transfer(accounts.bob, 337).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.transfer_all(accounts.charlie), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 0);
+            assert_eq!(contract.balance_of(accounts.charlie), 337);
+            assert_eq!(contract.transfer_all(accounts.charlie), Err(Error::InsufficientBalance));
+        }
+
+        #[ink::test]
+        fn whitelist_mode_restricts_transfers() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.transfer(accounts.bob, 100).unwrap();
+            contract.set_whitelist_only(true).unwrap();
+            contract.add_to_whitelist(accounts.bob).unwrap();
+            assert_eq!(contract.transfer(accounts.charlie, 10), Ok(()));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.transfer(accounts.charlie, 10), Err(Error::NotWhitelisted));
+            assert_eq!(contract.transfer(accounts.alice, 10), Ok(()));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            contract.add_to_whitelist(accounts.charlie).unwrap();
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.transfer(accounts.charlie, 10), Ok(()));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            contract.remove_from_whitelist(accounts.charlie).unwrap();
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.transfer(accounts.charlie, 10), Err(Error::NotWhitelisted));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            contract.set_whitelist_only(false).unwrap();
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.transfer(accounts.django, 10), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 70);
+        }
+
+        #[ink::test]
+        fn version_is_reported() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            assert_eq!(contract.version(), (0, 1, 0));
+            assert_eq!(contract.storage_version(), STORAGE_VERSION);
+
+            contract.storage_version = 0;
+            assert_eq!(contract.migrate(), Ok(()));
+            assert_eq!(contract.storage_version(), STORAGE_VERSION);
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.migrate(), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn flash_loan_cannot_charge_plain_account() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_capped(1000, Balance::MAX);
+            contract.transfer(accounts.bob, 100).unwrap();
+            contract.set_fee(100, accounts.eve).unwrap();
+
+            // The test engine treats every account as a plain account, so
+            // charlie cannot make bob pay the fee out of bob's own balance.
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(contract.flash_loan(accounts.bob, 500, Vec::new()), Err(Error::CallFailed));
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.flash_loan(accounts.bob, 500, Vec::new()), Err(Error::CallFailed));
+            assert_eq!(contract.balance_of(accounts.bob), 100);
+            assert_eq!(contract.total_supply(), 1000);
+        }
+
+        // The test engine cannot deploy a borrower contract, so the tests
+        // below drive the loan through `lend` with the callback standing in
+        // for `on_flash_loan`.
+        #[ink::test]
+        fn repaid_flash_loan_leaves_supply_counters_alone() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_capped(1000, 2000);
+            contract.transfer(accounts.bob, 10).unwrap();
+            contract.set_fee(100, accounts.eve).unwrap();
+
+            let result = contract.lend(accounts.bob, 1000, |contract, fee| {
+                assert_eq!(fee, 10);
+                assert_eq!(contract.balance_of(accounts.bob), 1010);
+                assert_eq!(contract.total_supply(), 2000);
+                Ok(())
+            });
+            assert_eq!(result, Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 0);
+            assert_eq!(contract.balance_of(accounts.eve), 10);
+            assert_eq!(contract.total_supply(), 1000);
+            assert_eq!((contract.total_minted(), contract.total_burned()), (1000, 0));
+            assert!(contract.check_supply_invariant());
+            assert!(!recorded_events().iter().any(|event| matches!(event, Event::CapReached(_))));
+
+            // The loan did not use up any room under the cap.
+            assert_eq!(contract.mint(accounts.charlie, 1000), Ok(()));
+            assert_eq!(contract.lend(accounts.bob, 1, |_, _| Ok(())), Err(Error::CapExceeded));
+        }
+
+        #[ink::test]
+        fn unrepaid_flash_loan_fails() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_capped(1000, Balance::MAX);
+            contract.set_fee(100, accounts.eve).unwrap();
+
+            // The borrower keeps the principal but cannot cover the fee.
+            assert_eq!(contract.lend(accounts.bob, 500, |_, _| Ok(())), Err(Error::FlashLoanNotRepaid));
+            // The borrower moves the principal away.
+            contract.transfer(accounts.charlie, 5).unwrap();
+            let result = contract.lend(accounts.charlie, 500, |contract, _| {
+                contract.move_tokens(&accounts.charlie, &accounts.django, 500)
+            });
+            assert_eq!(result, Err(Error::FlashLoanNotRepaid));
+        }
+
+        #[ink::test]
+        fn owner_is_deployer() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.django);
+            let contract = Token::new(1000);
+            assert_eq!(contract.owner(), accounts.django);
+        }
+
+        #[ink::test]
+        fn plain_new_cannot_mint() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            assert_eq!(contract.cap(), 1000);
+            assert_eq!(contract.mint(accounts.bob, 1), Err(Error::CapExceeded));
+            assert_eq!(contract.total_supply(), 1000);
+        }
+
+        #[ink::test]
+        fn transfers_are_rate_limited_per_block() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.set_max_transfers_per_block(2).unwrap();
+            assert_eq!(contract.transfer(accounts.bob, 10), Ok(()));
+            assert_eq!(contract.transfer(accounts.bob, 10), Ok(()));
+            assert_eq!(contract.transfer(accounts.bob, 10), Err(Error::RateLimited));
+
+            // Other senders have their own budget.
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.transfer(accounts.charlie, 5), Ok(()));
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(contract.transfer(accounts.bob, 10), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 25);
+        }
+
+        #[ink::test]
+        fn mint_whole_scales_by_decimals() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_with_metadata(0, None, None, 6);
+            assert_eq!(contract.mint_whole(accounts.bob, 5), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 5_000_000);
+            assert_eq!(contract.mint_whole(accounts.bob, Balance::MAX), Err(Error::Overflow));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.mint_whole(accounts.bob, 1), Err(Error::NotMinter));
+        }
+
+        #[ink::test]
+        fn burn_with_memo_records_memo() {
+            let mut contract = Token::new(1000);
+            assert_eq!(contract.burn_with_memo(100, [0xab; 32]), Ok(()));
+            assert_eq!(contract.total_supply(), 900);
+            assert!(matches!(
+                recorded_events().last(),
+                Some(Event::Burn(Burn { value: 100, memo, .. })) if *memo == [0xab; 32]
+            ));
+        }
+
+        #[ink::test]
+        fn spendable_balance_excludes_active_locks() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.transfer(accounts.bob, 100).unwrap();
+            contract.transfer(accounts.charlie, 100).unwrap();
+            assert_eq!(contract.spendable_balance_of(accounts.bob), 100);
+
+            contract.set_lock(accounts.bob, 60, 6).unwrap();
+            contract.set_lock(accounts.charlie, 500, 6).unwrap();
+            assert_eq!(contract.spendable_balance_of(accounts.bob), 40);
+            assert_eq!(contract.spendable_balance_of(accounts.charlie), 0);
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.spendable_balance_of(accounts.bob), 100);
+            assert_eq!(contract.spendable_balance_of(accounts.charlie), 100);
+        }
+
+        #[ink::test]
+        fn batch_transfer_can_emit_one_aggregate_event() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.set_batch_events(true).unwrap();
+            let before = recorded_events().len();
+            let recipients = vec![accounts.bob, accounts.charlie, accounts.django];
+            contract.batch_transfer(recipients.clone(), vec![10, 20, 30]).unwrap();
+
+            let events = recorded_events();
+            assert_eq!(events.len(), before + 1);
+            match events.last() {
+                Some(Event::TransferBatch(TransferBatch { from, to_list, values })) => {
+                    assert_eq!(*from, accounts.alice);
+                    assert_eq!(*to_list, recipients);
+                    assert_eq!(*values, vec![10, 20, 30]);
+                }
+                _ => panic!("expected a TransferBatch event"),
+            }
+            assert_eq!(contract.balance_of(accounts.django), 30);
+
+            // Plain transfers keep their standard event.
+            contract.transfer(accounts.bob, 1).unwrap();
+            assert!(matches!(recorded_events().last(), Some(Event::Transfer(_))));
+        }
+
+        #[ink::test]
+        fn batch_event_adds_up_with_fee_tax_and_burn() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(10_000);
+            contract.transfer(accounts.bob, 5000).unwrap();
+            contract.set_fee(100, accounts.eve).unwrap();
+            contract.set_tax(100, 5_000).unwrap();
+            contract.set_burn_rate(100).unwrap();
+            contract.set_batch_events(true).unwrap();
+            let before = recorded_events().len();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            let recipients = vec![accounts.charlie, accounts.django];
+            contract.batch_transfer(recipients.clone(), vec![1000, 2000]).unwrap();
+
+            // Rebuild every balance change from the events alone.
+            let mut deltas: Vec<(Option<AccountId>, i128)> = Vec::new();
+            let mut apply = |account: Option<AccountId>, delta: i128| match deltas.iter_mut().find(|(a, _)| *a == account) {
+                Some((_, total)) => *total += delta,
+                None => deltas.push((account, delta)),
+            };
+            for event in &recorded_events()[before..] {
+                match event {
+                    Event::Transfer(Transfer { from, to, value, .. }) => {
+                        apply(*from, -(*value as i128));
+                        apply(*to, *value as i128);
+                    }
+                    Event::TransferBatch(TransferBatch { from, to_list, values }) => {
+                        assert_eq!(*to_list, recipients);
+                        assert_eq!(*values, vec![970, 1940]);
+                        for (to, value) in to_list.iter().zip(values) {
+                            apply(Some(*from), -(*value as i128));
+                            apply(Some(*to), *value as i128);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            let delta_of = |account: Option<AccountId>| {
+                deltas.iter().find(|(a, _)| *a == account).map_or(0, |(_, delta)| *delta)
+            };
+            assert_eq!(delta_of(Some(accounts.bob)), -3000);
+            assert_eq!(delta_of(Some(accounts.charlie)), 970);
+            assert_eq!(delta_of(Some(accounts.django)), 1940);
+            assert_eq!(delta_of(Some(accounts.eve)), 45);
+            assert_eq!(delta_of(None), 45);
+            assert_eq!(contract.balance_of(accounts.bob), 2000);
+            assert_eq!(contract.balance_of(accounts.charlie), 970);
+            assert_eq!(contract.balance_of(accounts.eve), 45);
+        }
+
+        #[ink::test]
+        fn mint_with_sig_accepts_each_nonce_once() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_capped(1000, Balance::MAX);
+            contract.set_authorizer(Some(ecdsa_account([0x22; 32]))).unwrap();
+            let signature = sign_hash([0x22; 32], contract.mint_hash(&accounts.bob, 500, 7));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(contract.mint_with_sig(accounts.bob, 501, 7, signature), Err(Error::InvalidSignature));
+            assert_eq!(contract.mint_with_sig(accounts.bob, 500, 7, signature), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 500);
+            assert_eq!(contract.mint_with_sig(accounts.bob, 500, 7, signature), Err(Error::NonceUsed));
+        }
+
+        #[ink::test]
+        fn mint_with_sig_rejects_other_signers() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_capped(1000, Balance::MAX);
+            let signature = sign_hash([0x33; 32], contract.mint_hash(&accounts.bob, 500, 1));
+            assert_eq!(contract.mint_with_sig(accounts.bob, 500, 1, signature), Err(Error::InvalidSignature));
+
+            contract.set_authorizer(Some(ecdsa_account([0x22; 32]))).unwrap();
+            assert_eq!(contract.mint_with_sig(accounts.bob, 500, 1, signature), Err(Error::InvalidSignature));
+            assert_eq!(contract.total_supply(), 1000);
+        }
+
+        #[ink::test]
+        fn bridge_lock_burns_and_emits() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            assert_eq!(contract.bridge_lock(300, 2, [0x42; 32]), Ok(()));
+            assert_eq!(contract.balance_of(accounts.alice), 700);
+            assert_eq!(contract.total_supply(), 700);
+            assert!(matches!(
+                recorded_events().last(),
+                Some(Event::BridgeLocked(BridgeLocked { amount: 300, dest_chain: 2, dest_addr, .. }))
+                    if *dest_addr == [0x42; 32]
+            ));
+            assert_eq!(contract.bridge_lock(701, 2, [0x42; 32]), Err(Error::InsufficientBalance));
+        }
+
+        #[ink::test]
+        fn bridge_unlock_rejects_duplicates() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.bridge_lock(300, 2, [0x42; 32]).unwrap();
+
+            assert_eq!(contract.bridge_unlock(accounts.bob, 300, [0x01; 32]), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 300);
+            assert_eq!(contract.bridge_unlock(accounts.bob, 300, [0x01; 32]), Err(Error::AlreadyProcessed));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.bridge_unlock(accounts.bob, 1, [0x02; 32]), Err(Error::NotMinter));
+            assert_eq!(contract.total_supply(), 1000);
+        }
+
+        #[ink::test]
+        fn bridge_unlock_counts_against_minter_cap() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_capped(1000, 10_000);
+            contract.grant_minter(accounts.bob).unwrap();
+            contract.set_minter_cap(accounts.bob, Some(100)).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.bridge_unlock(accounts.charlie, 60, [0x01; 32]), Ok(()));
+            assert_eq!(contract.mint(accounts.charlie, 30), Ok(()));
+            assert_eq!(
+                contract.bridge_unlock(accounts.charlie, 11, [0x02; 32]),
+                Err(Error::MinterCapExceeded)
+            );
+            assert_eq!(contract.bridge_unlock(accounts.charlie, 10, [0x03; 32]), Ok(()));
+            assert_eq!(contract.minter_allowance(accounts.bob), (Some(100), 100));
+            assert_eq!(contract.balance_of(accounts.charlie), 100);
+        }
+
+        #[ink::test]
+        fn expired_allowance_reads_as_zero() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.approve_with_expiry(accounts.bob, 100, 6).unwrap();
+            assert_eq!(contract.allowance_expiry(accounts.alice, accounts.bob), Some(6));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.transfer_from(accounts.alice, accounts.bob, 40), Ok(()));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.transfer_from(accounts.alice, accounts.bob, 10), Ok(()));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 0);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.bob, 10),
+                Err(Error::InsufficientAllowance)
+            );
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            contract.approve(accounts.bob, 100).unwrap();
+            assert_eq!(contract.allowance_expiry(accounts.alice, accounts.bob), None);
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 100);
+        }
+
+        #[ink::test]
+        fn simulate_transfer_previews_without_writing() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.set_fee(250, accounts.eve).unwrap();
+            let events = recorded_events().len();
+
+            assert_eq!(contract.simulate_transfer(accounts.alice, accounts.bob, 400), Ok(390));
+            assert_eq!(
+                contract.simulate_transfer(accounts.alice, accounts.bob, 1001),
+                Err(Error::InsufficientBalance)
+            );
+            assert_eq!(contract.balance_of(accounts.bob), 0);
+            assert_eq!(recorded_events().len(), events);
+
+            contract.set_lock(accounts.alice, 700, 6).unwrap();
+            assert_eq!(
+                contract.simulate_transfer(accounts.alice, accounts.bob, 400),
+                Err(contract.transfer(accounts.bob, 400).unwrap_err())
+            );
+        }
+
+        #[ink::test]
+        fn decimals_are_locked_after_deployment() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_with_metadata(1000, None, None, 6);
+            assert!(contract.decimals_locked());
+
+            contract.transfer_units(accounts.bob, 0, 5).unwrap();
+            contract.mint_whole(accounts.bob, 1).unwrap();
+            contract.set_fee(100, accounts.eve).unwrap();
+            contract.migrate().unwrap();
+            assert_eq!(contract.token_decimals(), 6);
+            assert_eq!(contract.metadata().2, 6);
+        }
+
+        #[ink::test]
+        fn transfer_from_tracks_spent_amounts() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.approve(accounts.bob, UNLIMITED_ALLOWANCE).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            contract.transfer_from(accounts.alice, accounts.charlie, 30).unwrap();
+            contract.transfer_from(accounts.alice, accounts.bob, 45).unwrap();
+            assert_eq!(contract.total_spent_by(accounts.alice, accounts.bob), 75);
+            assert_eq!(contract.total_spent_by(accounts.alice, accounts.charlie), 0);
+        }
+
+        #[ink::test]
+        fn approvals_fail_while_paused() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.approve(accounts.bob, 50).unwrap();
+            contract.pause().unwrap();
+
+            assert_eq!(contract.approve(accounts.bob, 100), Err(Error::Paused));
+            assert_eq!(contract.increase_allowance(accounts.bob, 10), Err(Error::Paused));
+            assert_eq!(contract.safe_approve(accounts.bob, 50, 100), Err(Error::Paused));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 50);
+            assert_eq!(contract.decrease_allowance(accounts.bob, 10), Ok(()));
+
+            contract.unpause().unwrap();
+            assert_eq!(contract.approve(accounts.bob, 100), Ok(()));
+            assert_eq!(contract.increase_allowance(accounts.bob, 10), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 110);
+        }
+
+        #[ink::test]
+        fn zero_transfers_can_be_rejected() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            assert!(contract.allow_zero_transfers());
+            assert_eq!(contract.transfer(accounts.bob, 0), Ok(()));
+            assert!(matches!(
+                recorded_events().last(),
+                Some(Event::Transfer(Transfer { value: 0, .. }))
+            ));
+
+            contract.set_allow_zero_transfers(false).unwrap();
+            assert_eq!(contract.transfer(accounts.bob, 0), Err(Error::ZeroAmount));
+            assert_eq!(contract.transfer(accounts.bob, 1), Ok(()));
+        }
+
+        #[ink::test]
+        fn account_info_bundles_account_state() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.transfer(accounts.bob, 200).unwrap();
+            contract.freeze_account(accounts.bob).unwrap();
+            contract.set_lock(accounts.bob, 150, 6).unwrap();
+            assert_eq!(contract.account_info(accounts.bob), (200, true, 150));
+            assert_eq!(contract.account_info(accounts.charlie), (0, false, 0));
+        }
+
+        #[ink::test]
+        fn burn_rate_shrinks_supply_on_transfer() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(10_000);
+            contract.set_burn_rate(200).unwrap();
+            // Transfers from the owner are exempt.
+            contract.transfer(accounts.bob, 5_000).unwrap();
+            assert_eq!(contract.total_supply(), 10_000);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            contract.transfer(accounts.charlie, 1_000).unwrap();
+            assert_eq!(contract.balance_of(accounts.bob), 4_000);
+            assert_eq!(contract.balance_of(accounts.charlie), 980);
+            assert_eq!(contract.total_supply(), 9_980);
+            assert_eq!(contract.total_burned(), 20);
+            assert!(matches!(
+                recorded_events().last(),
+                Some(Event::Burn(Burn { value: 20, .. }))
+            ));
+        }
+
+        #[ink::test]
+        fn burn_rate_rounds_down_on_small_transfers() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(10_000);
+            contract.set_burn_rate(200).unwrap();
+            contract.transfer(accounts.bob, 100).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            contract.transfer(accounts.charlie, 49).unwrap();
+            assert_eq!(contract.balance_of(accounts.charlie), 49);
+            contract.transfer(accounts.charlie, 50).unwrap();
+            assert_eq!(contract.balance_of(accounts.charlie), 98);
+            assert_eq!(contract.total_supply(), 9_999);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(contract.set_burn_rate(MAX_FEE_BPS + 1), Err(Error::FeeTooHigh));
+        }
+
+        #[ink::test]
+        fn recover_foreign_token_is_recovery_authority_only() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_with_recovery_authority(1000, accounts.frank);
+            let foreign = AccountId::from([0xf0; 32]);
+            assert_eq!(
+                contract.recover_foreign_token(foreign, accounts.bob, 10),
+                Err(Error::NotRecoveryAuthority)
+            );
+
+            // The off-chain engine has no contracts, so the call cannot be made.
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.frank);
+            assert_eq!(contract.recover_foreign_token(foreign, accounts.bob, 10), Err(Error::CallFailed));
+        }
+
+        #[ink::test]
+        fn random_operations_never_trap_and_keep_supply_consistent() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let holders = [accounts.alice, accounts.bob, accounts.charlie, accounts.django, accounts.eve];
+            let mut contract = Token::new_capped(1_000_000, Balance::MAX);
+            contract.set_fee(150, accounts.eve).unwrap();
+            contract.set_burn_rate(75).unwrap();
+
+            // xorshift64, so that the sequence is reproducible.
+            let mut state = 0x2545_f491_4f6c_dd1du64;
+            let mut next = move || {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state
+            };
+            for _ in 0..2_000 {
+                let op = next() % 5;
+                let from = holders[(next() % 5) as usize];
+                let to = holders[(next() % 5) as usize];
+                let value = match next() % 4 {
+                    0 => Balance::MAX - Balance::from(next() % 3),
+                    1 => 0,
+                    _ => Balance::from(next() % 50_000),
+                };
+
+                ink_env::test::set_caller::<ink_env::DefaultEnvironment>(from);
+                let _ = match op {
+                    0 => contract.transfer(to, value),
+                    1 => contract.burn(value),
+                    2 => contract.approve(to, value),
+                    3 => {
+                        ink_env::test::set_caller::<ink_env::DefaultEnvironment>(to);
+                        contract.transfer_from(from, to, value)
+                    }
+                    _ => {
+                        ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+                        contract.mint(to, value)
+                    }
+                };
+
+                let sum = holders
+                    .iter()
+                    .try_fold(0, |sum: Balance, holder| sum.checked_add(contract.balance_of(*holder)));
+                assert_eq!(sum, Some(contract.total_supply()));
+            }
+        }
+
+        #[ink::test]
+        fn approve_returning_reports_previous_allowance() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.approve(accounts.bob, 100).unwrap();
+            assert_eq!(contract.approve_returning(accounts.bob, 50), Ok(100));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 50);
+            assert_eq!(contract.approve_returning(accounts.charlie, 10), Ok(0));
+        }
+
+        #[ink::test]
+        fn mint_events_use_configured_sender() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            Token::new_with_mint_sender(1000, false);
+            assert!(matches!(
+                recorded_events().last(),
+                Some(Event::Transfer(Transfer { from: None, value: 1000, .. }))
+            ));
+
+            let mut contract = Token::new_with_mint_sender(1000, true);
+            let zero = AccountId::from([0x0; 32]);
+            assert!(matches!(
+                recorded_events().last(),
+                Some(Event::Transfer(Transfer { from: Some(from), value: 1000, .. })) if *from == zero
+            ));
+            contract.mint(accounts.bob, 50).unwrap();
+            let events = recorded_events();
+            assert!(matches!(
+                &events[events.len() - 2],
+                Event::Transfer(Transfer { from: Some(from), to: Some(to), value: 50, .. }) if *from == zero && *to == accounts.bob
+            ));
+        }
+
+        #[ink::test]
+        fn increase_allowance_saturating_caps_at_unlimited() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.approve(accounts.bob, UNLIMITED_ALLOWANCE - 10).unwrap();
+            assert_eq!(contract.increase_allowance(accounts.bob, 11), Err(Error::Overflow));
+
+            assert_eq!(contract.increase_allowance_saturating(accounts.bob, 10), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), UNLIMITED_ALLOWANCE);
+            assert_eq!(contract.increase_allowance_saturating(accounts.bob, 1), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), UNLIMITED_ALLOWANCE);
+            assert!(matches!(
+                recorded_events().last(),
+                Some(Event::Approval(Approval { value: UNLIMITED_ALLOWANCE, .. }))
+            ));
+
+            assert_eq!(contract.increase_allowance_saturating(accounts.charlie, 5), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.charlie), 5);
+        }
+
+        #[ink::test]
+        fn trade_cooldown_spaces_out_transfers() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.set_trade_cooldown(12).unwrap();
+            // The owner is exempt.
+            contract.transfer(accounts.bob, 100).unwrap();
+            contract.transfer(accounts.bob, 100).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.transfer(accounts.charlie, 10), Ok(()));
+            assert_eq!(contract.transfer(accounts.charlie, 10), Err(Error::CooldownActive));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.transfer(accounts.charlie, 10), Err(Error::CooldownActive));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.transfer(accounts.charlie, 10), Ok(()));
+            assert_eq!(contract.balance_of(accounts.charlie), 20);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            contract.set_trade_cooldown(0).unwrap();
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.transfer(accounts.charlie, 10), Ok(()));
+            assert_eq!(contract.transfer(accounts.charlie, 10), Ok(()));
+        }
+
+        #[ink::test]
+        fn supply_invariant_holds_across_operations() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_capped(1000, Balance::MAX);
+            assert!(contract.check_supply_invariant());
+            contract.set_fee(100, accounts.eve).unwrap();
+            contract.set_burn_rate(50).unwrap();
+            contract.transfer(accounts.bob, 500).unwrap();
+            contract.mint(accounts.charlie, 300).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            contract.transfer(accounts.charlie, 200).unwrap();
+            contract.burn(100).unwrap();
+            assert!(contract.check_supply_invariant());
+            assert_eq!(contract.total_supply(), 1199);
+
+            contract.total_supply += 1;
+            assert!(!contract.check_supply_invariant());
+        }
+
+        #[ink::test]
+        fn tax_split_between_burn_and_collector() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            // Mapping entries outlive each contract in the off-chain engine, so
+            // every case uses its own recipient and collector.
+            let cases = [
+                (0, 100, 0, accounts.bob, accounts.charlie),
+                (5_000, 50, 50, accounts.django, accounts.eve),
+                (10_000, 0, 100, accounts.frank, AccountId::from([0xee; 32])),
+            ];
+            for (burn_share_bps, collected, burned, to, collector) in cases {
+                let mut contract = Token::new(100_000);
+                contract.set_fee(0, collector).unwrap();
+                contract.set_tax(100, burn_share_bps).unwrap();
+                contract.transfer(to, 10_000).unwrap();
+                assert_eq!(contract.balance_of(to), 9_900);
+                assert_eq!(contract.balance_of(collector), collected);
+                assert_eq!(contract.total_supply(), 100_000 - burned);
+            }
+        }
+
+        #[ink::test]
+        fn set_tax_rejects_out_of_range_values() {
+            let mut contract = Token::new(1000);
+            assert_eq!(contract.set_tax(MAX_FEE_BPS + 1, 0), Err(Error::FeeTooHigh));
+            assert_eq!(contract.set_tax(100, 10_001), Err(Error::InvalidFraction));
+            assert_eq!(contract.tax_bps(), 0);
+        }
+
+        #[ink::test]
+        fn self_balance_reads_contract_account() {
+            let contract_id = set_contract_account();
+            let mut contract = Token::new(1000);
+            assert_eq!(contract.self_balance(), 0);
+            contract.transfer(contract_id, 300).unwrap();
+            assert_eq!(contract.self_balance(), 300);
+        }
+
+        #[ink::test]
+        fn minter_cap_limits_each_minter() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_capped(1000, Balance::MAX);
+            contract.grant_minter(accounts.bob).unwrap();
+            contract.grant_minter(accounts.charlie).unwrap();
+            contract.set_minter_cap(accounts.bob, Some(100)).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.mint(accounts.eve, 60), Ok(()));
+            assert_eq!(contract.mint(accounts.eve, 41), Err(Error::MinterCapExceeded));
+            assert_eq!(contract.mint(accounts.eve, 40), Ok(()));
+            assert_eq!(contract.mint(accounts.eve, 1), Err(Error::MinterCapExceeded));
+            assert_eq!(contract.minter_allowance(accounts.bob), (Some(100), 100));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(contract.mint(accounts.eve, 500), Ok(()));
+            assert_eq!(contract.balance_of(accounts.eve), 600);
+
+            assert_eq!(contract.set_minter_cap(accounts.bob, None), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn timelocked_action_waits_for_delay() {
+            let mut contract = Token::new(1000);
+            contract.set_timelock_delay(12).unwrap();
+            contract.queue_action(100, [0xab; 32]).unwrap();
+            assert_eq!(contract.queued_action(100), Some(([0xab; 32], 0)));
+
+            assert_eq!(contract.execute_action(100), Err(Error::TimelockNotElapsed));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.execute_action(100), Err(Error::TimelockNotElapsed));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.execute_action(100), Ok(()));
+            assert!(matches!(
+                recorded_events().last(),
+                Some(Event::ActionExecuted(ActionExecuted { action_id: 100, params_hash })) if *params_hash == [0xab; 32]
+            ));
+            assert_eq!(contract.execute_action(100), Err(Error::ActionNotQueued));
+            assert_eq!(contract.execute_action(ACTION_UPGRADE), Err(Error::ReservedAction));
+        }
+
+        #[ink::test]
+        fn cancelled_action_cannot_execute() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.set_timelock_delay(12).unwrap();
+            contract.queue_action(7, [0x01; 32]).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.cancel_action(7), Err(Error::NotOwner));
+            assert_eq!(contract.execute_action(7), Err(Error::NotOwner));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(contract.cancel_action(7), Ok(()));
+            assert_eq!(contract.queued_action(7), None);
+            assert_eq!(contract.execute_action(7), Err(Error::ActionNotQueued));
+            assert_eq!(contract.cancel_action(7), Err(Error::ActionNotQueued));
+        }
+
+        #[ink::test]
+        fn revoke_allowance_removes_storage_entry() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.approve_with_expiry(accounts.bob, 100, 50).unwrap();
+            assert_eq!(contract.revoke_allowance(accounts.bob), Ok(()));
+            assert_eq!(contract.allowances.get((accounts.alice, accounts.bob)), None);
+            assert_eq!(contract.allowance_expiry(accounts.alice, accounts.bob), None);
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 0);
+            assert!(matches!(
+                recorded_events().last(),
+                Some(Event::Approval(Approval { value: 0, .. }))
+            ));
+        }
+
+        #[ink::test]
+        fn total_supply_whole_splits_by_decimals() {
+            let contract = Token::new_with_metadata(1_234_567, None, None, 4);
+            assert_eq!(contract.total_supply_whole(), (123, 4567));
+
+            let contract = Token::new_with_metadata(1_234_567, None, None, 0);
+            assert_eq!(contract.total_supply_whole(), (1_234_567, 0));
+        }
+
+        #[ink::test]
+        fn recovery_authority_and_owner_have_separate_duties() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_with_recovery_authority(1000, accounts.django);
+            assert!(contract.is_recovery_authority(accounts.django));
+            assert!(!contract.is_recovery_authority(accounts.alice));
+            contract.transfer(accounts.bob, 300).unwrap();
+
+            assert_eq!(
+                contract.force_transfer(accounts.bob, accounts.alice, 100),
+                Err(Error::NotRecoveryAuthority)
+            );
+            assert_eq!(contract.rescue_self_balance(accounts.alice), Err(Error::NotRecoveryAuthority));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.django);
+            assert_eq!(contract.set_fee(100, accounts.django), Err(Error::NotOwner));
+            assert_eq!(contract.force_transfer(accounts.bob, accounts.charlie, 100), Ok(()));
+            assert_eq!(contract.balance_of(accounts.charlie), 100);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "recovery authority must differ from the owner")]
+        fn recovery_authority_cannot_be_deployer() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            Token::new_with_recovery_authority(1000, accounts.alice);
+        }
+
+        #[ink::test]
+        fn zero_allowances_free_their_storage() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.approve(accounts.bob, 100).unwrap();
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            contract.transfer_from(accounts.alice, accounts.bob, 100).unwrap();
+            assert_eq!(contract.allowances.get((accounts.alice, accounts.bob)), None);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            contract.approve(accounts.charlie, 50).unwrap();
+            contract.decrease_allowance(accounts.charlie, 50).unwrap();
+            assert_eq!(contract.allowances.get((accounts.alice, accounts.charlie)), None);
+
+            contract.approve(accounts.django, 50).unwrap();
+            contract.approve(accounts.django, 0).unwrap();
+            assert_eq!(contract.allowances.get((accounts.alice, accounts.django)), None);
+        }
+
+        #[ink::test]
+        fn holders_page_skips_emptied_accounts() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.transfer(accounts.bob, 100).unwrap();
+            contract.transfer(accounts.charlie, 200).unwrap();
+            contract.transfer(accounts.django, 300).unwrap();
+            contract.transfer(accounts.eve, 50).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+            contract.transfer(accounts.bob, 200).unwrap();
+
+            assert_eq!(
+                contract.holders_page(0, 2),
+                vec![(accounts.alice, 350), (accounts.bob, 300)]
+            );
+            assert_eq!(contract.holders_page(2, 2), vec![(accounts.django, 300)]);
+            assert_eq!(contract.holders_page(4, 2), vec![(accounts.eve, 50)]);
+            assert_eq!(contract.holders_page(5, 2), vec![]);
+            assert_eq!(
+                contract.holders_page(3, u32::MAX),
+                vec![(accounts.django, 300), (accounts.eve, 50)]
+            );
+            assert_eq!(contract.holders_page(u32::MAX, u32::MAX), vec![]);
+        }
+
+        #[ink::test]
+        fn round_fees_up_uses_ceiling_division() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(10_000);
+            contract.set_fee(100, accounts.eve).unwrap();
+            assert_eq!(contract.simulate_transfer(accounts.alice, accounts.bob, 999), Ok(990));
+
+            contract.set_round_fees_up(true).unwrap();
+            assert_eq!(contract.simulate_transfer(accounts.alice, accounts.bob, 999), Ok(989));
+            assert_eq!(contract.simulate_transfer(accounts.alice, accounts.bob, 1_000), Ok(990));
+            contract.transfer(accounts.bob, 999).unwrap();
+            assert_eq!(contract.balance_of(accounts.eve), 10);
+        }
+
+        #[ink::test]
+        fn round_fees_up_never_takes_more_than_value() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(10_000);
+            contract.set_fee(100, accounts.eve).unwrap();
+            contract.set_burn_rate(100).unwrap();
+            contract.set_tax(100, 5_000).unwrap();
+            contract.set_round_fees_up(true).unwrap();
+            contract.transfer(accounts.bob, 100).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.simulate_transfer(accounts.bob, accounts.charlie, 1), Ok(0));
+            assert_eq!(contract.transfer(accounts.charlie, 1), Ok(()));
+            assert_eq!(contract.balance_of(accounts.charlie), 0);
+            assert!(contract.check_supply_invariant());
+        }
+
+        #[ink::test]
+        fn previous_owner_cannot_force_transfer() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            assert!(!contract.is_recovery_authority(accounts.alice));
+            assert_eq!(contract.recovery_authority(), AccountId::from([0x0; 32]));
+            contract.transfer(accounts.bob, 300).unwrap();
+
+            contract.transfer_ownership(accounts.charlie).unwrap();
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+            contract.accept_ownership().unwrap();
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(
+                contract.force_transfer(accounts.bob, accounts.alice, 100),
+                Err(Error::NotRecoveryAuthority)
+            );
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+            contract.renounce_ownership().unwrap();
+            assert_eq!(
+                contract.force_transfer(accounts.bob, accounts.charlie, 100),
+                Err(Error::NotRecoveryAuthority)
+            );
+            assert_eq!(contract.balance_of(accounts.bob), 300);
+        }
+
+        #[ink::test]
+        fn upgrade_needs_queued_elapsed_action() {
+            let mut contract = Token::new(1000);
+            contract.set_timelock_delay(12).unwrap();
+            assert_eq!(contract.upgrade([0x42; 32]), Err(Error::ActionNotQueued));
+
+            contract.queue_action(ACTION_UPGRADE, params_hash(&[0x42u8; 32])).unwrap();
+            assert_eq!(contract.upgrade([0x43; 32]), Err(Error::ParamsMismatch));
+            assert_eq!(contract.upgrade([0x42; 32]), Err(Error::TimelockNotElapsed));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.upgrade([0x42; 32]), Err(Error::TimelockNotElapsed));
+            // The off-chain engine cannot swap code, so the elapsed upgrade
+            // itself is not exercised here.
+            assert!(contract.queued_action(ACTION_UPGRADE).is_some());
+        }
+
+        #[ink::test]
+        fn high_fee_needs_queued_elapsed_action() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.set_timelock_delay(12).unwrap();
+            assert_eq!(contract.set_fee(TIMELOCK_FEE_BPS, accounts.eve), Ok(()));
+            assert_eq!(contract.set_fee(800, accounts.eve), Err(Error::ActionNotQueued));
+
+            contract.queue_action(ACTION_SET_FEE, params_hash(&(800u16, accounts.eve))).unwrap();
+            assert_eq!(contract.set_fee(800, accounts.bob), Err(Error::ParamsMismatch));
+            assert_eq!(contract.set_fee(800, accounts.eve), Err(Error::TimelockNotElapsed));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.set_fee(800, accounts.eve), Ok(()));
+            assert_eq!(contract.fee_bps(), 800);
+            assert_eq!(contract.queued_action(ACTION_SET_FEE), None);
+
+            contract.set_fee(0, accounts.eve).unwrap();
+            assert_eq!(contract.set_fee(800, accounts.eve), Err(Error::ActionNotQueued));
+        }
+
+        #[ink::test]
+        fn shortening_timelock_needs_queued_elapsed_action() {
+            let mut contract = Token::new(1000);
+            contract.set_timelock_delay(12).unwrap();
+            assert_eq!(contract.set_timelock_delay(0), Err(Error::ActionNotQueued));
+
+            contract.queue_action(ACTION_LOWER_TIMELOCK, params_hash(&0u64)).unwrap();
+            assert_eq!(contract.set_timelock_delay(0), Err(Error::TimelockNotElapsed));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.set_timelock_delay(0), Ok(()));
+            assert_eq!(contract.timelock_delay(), 0);
+            assert_eq!(contract.queue_action(100, [0x01; 32]), Err(Error::TimelockDisabled));
+        }
+
+        #[ink::test]
+        fn nothing_can_be_queued_without_a_delay() {
+            let mut contract = Token::new(1000);
+            assert_eq!(contract.timelock_delay(), 0);
+            assert_eq!(
+                contract.queue_action(ACTION_UPGRADE, params_hash(&[0x42u8; 32])),
+                Err(Error::TimelockDisabled)
+            );
+            assert_eq!(contract.upgrade([0x42; 32]), Err(Error::ActionNotQueued));
+        }
+
+        #[ink::test]
+        fn combined_rate_above_threshold_needs_queued_action() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.set_timelock_delay(12).unwrap();
+            contract.set_fee(300, accounts.eve).unwrap();
+            contract.set_burn_rate(200).unwrap();
+            assert_eq!(contract.set_tax(1000, 0), Err(Error::ActionNotQueued));
+            assert_eq!(contract.set_tax(1, 0), Err(Error::ActionNotQueued));
+            assert_eq!(contract.tax_bps(), 0);
+
+            contract.queue_action(ACTION_SET_TAX, params_hash(&(1000u16, 10_000u16))).unwrap();
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.set_tax(1000, 10_000), Ok(()));
+            // Redirecting the burned share of the tax to the collector counts
+            // as an increase too.
+            assert_eq!(contract.set_tax(1000, 0), Err(Error::ActionNotQueued));
+            assert_eq!(contract.set_burn_rate(300), Err(Error::ActionNotQueued));
+            assert_eq!(contract.set_fee(300, accounts.bob), Err(Error::ActionNotQueued));
+
+            // Lowering any part is never delayed.
+            assert_eq!(contract.set_burn_rate(0), Ok(()));
+            assert_eq!(contract.set_tax(0, 0), Ok(()));
+            assert_eq!(contract.set_burn_rate(200), Ok(()));
+        }
+
+        #[ink::test]
+        fn mint_pause_stops_every_mint_path() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new_with_faucet(1000, 10, 0);
+            contract.set_mint_paused(true).unwrap();
+            assert_eq!(contract.claim(), Err(Error::Paused));
+            assert_eq!(contract.bridge_unlock(accounts.bob, 10, [0x01; 32]), Err(Error::Paused));
+            assert_eq!(contract.total_supply(), 1000);
+
+            contract.set_mint_paused(false).unwrap();
+            assert_eq!(contract.claim(), Ok(()));
+            assert_eq!(contract.total_supply(), 1010);
+        }
+
+        #[ink::test]
+        fn account_and_hash_settings_emit_config_events() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.set_fee(100, accounts.eve).unwrap();
+            contract.set_fee(100, accounts.bob).unwrap();
+            let events = recorded_events();
+            match &events[events.len() - 2] {
+                Event::ConfigBytesChanged(ConfigBytesChanged { key, old, new, by }) => {
+                    assert_eq!(key, b"fee_col\0");
+                    assert_eq!((old, new), (accounts.eve.as_ref(), accounts.bob.as_ref()));
+                    assert_eq!(*by, accounts.alice);
+                }
+                _ => panic!("expected a ConfigBytesChanged event"),
+            }
+
+            contract.set_authorizer(Some(accounts.charlie)).unwrap();
+            assert!(matches!(
+                recorded_events().last(),
+                Some(Event::ConfigBytesChanged(ConfigBytesChanged { key, old, .. }))
+                    if key == b"authrzr\0" && *old == [0; 32]
+            ));
+            contract.set_merkle_root([0x11; 32]).unwrap();
+            assert!(matches!(
+                recorded_events().last(),
+                Some(Event::ConfigBytesChanged(ConfigBytesChanged { key, new, .. }))
+                    if key == b"merkle\0\0" && *new == [0x11; 32]
+            ));
+            contract.create_coupon([0x22; 32], 50).unwrap();
+            assert!(matches!(
+                recorded_events().last(),
+                Some(Event::ConfigChanged(ConfigChanged { key, old: 0, new: 50, .. })) if key == b"coupon\0\0"
+            ));
+            contract.exclude_from_circulation(accounts.django).unwrap();
+            contract.include_in_circulation(accounts.django).unwrap();
+            assert!(matches!(
+                recorded_events().last(),
+                Some(Event::ConfigChanged(ConfigChanged { key, old: 1, new: 0, .. })) if key == b"excluded"
+            ));
+        }
+
+        #[ink::test]
+        fn only_fixed_supply_constructors_cap_at_initial_supply() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(Token::new(100).cap(), 100);
+            assert_eq!(Token::new_with_min_supply(100, 1).cap(), 100);
+            assert_eq!(Token::new_with_metadata(100, None, None, 18).cap(), Balance::MAX);
+            assert_eq!(Token::new_whole_tokens(1, 2).cap(), Balance::MAX);
+            assert_eq!(Token::new_with_zero_address_check(100, true).cap(), Balance::MAX);
+            assert_eq!(Token::new_with_faucet(100, 10, 0).cap(), Balance::MAX);
+            assert_eq!(Token::new_with_mint_sender(100, true).cap(), Balance::MAX);
+            assert_eq!(Token::new_with_recovery_authority(100, accounts.frank).cap(), Balance::MAX);
+        }
+
+        #[ink::test]
+        fn increasing_expired_allowance_clears_expiry() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = Token::new(1000);
+            contract.approve_with_expiry(accounts.bob, 100, 6).unwrap();
+            contract.increase_allowance(accounts.bob, 10).unwrap();
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 110);
+            assert_eq!(contract.allowance_expiry(accounts.alice, accounts.bob), Some(6));
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 0);
+            contract.increase_allowance(accounts.bob, 25).unwrap();
+            assert_eq!(contract.allowance_expiry(accounts.alice, accounts.bob), None);
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 25);
+            assert!(matches!(
+                recorded_events().last(),
+                Some(Event::Approval(Approval { value: 25, .. }))
+            ));
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.transfer_from(accounts.alice, accounts.bob, 25), Ok(()));
         }
     }
 }